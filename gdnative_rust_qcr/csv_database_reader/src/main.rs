@@ -9,6 +9,7 @@ use serde::Deserialize;
 use csv::Reader;
 
 use rusqlite::Connection;
+use sql_database::db::DBQualityControl;
 
 #[derive(Debug,Deserialize)]
 struct Row {
@@ -91,62 +92,11 @@ fn get_first_arg() -> Result<OsString, Box<dyn Error>> {
     }
 }
 
+// Rebuilds the database from scratch, the schema is owned by sql_database.
 fn setup_database(conn: &Connection) -> Result<(), rusqlite::Error>{
 
-    drop_database(&conn);
-
-    conn.execute_batch(
-        "BEGIN;
-        CREATE TABLE IF NOT EXISTS section (
-            id INTEGER PRIMARY KEY,
-            section_name TEXT NOT NULL UNIQUE
-        );
-        CREATE TABLE IF NOT EXISTS job_type (
-            id INTEGER PRIMARY KEY,
-            job_type_name TEXT NOT NULL UNIQUE
-        );
-        CREATE TABLE IF NOT EXISTS additive_section (
-            section_id INTEGER,
-            FOREIGN KEY (section_id)
-                REFERENCES section (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION
-        );
-        CREATE TABLE IF NOT EXISTS specification (
-            id INTEGER PRIMARY KEY,
-            specification_content TEXT NOT NULL UNIQUE,
-            section_id INTEGER,
-            FOREIGN KEY (section_id)
-                REFERENCES section (id)
-                    ON DELETE SET NULL
-                    ON UPDATE NO ACTION
-        );
-        CREATE TABLE IF NOT EXISTS job_specification (
-            job_type_id INTEGER,
-            specification_id INTEGER,
-            PRIMARY KEY (job_type_id, specification_id),
-            FOREIGN KEY (job_type_id)
-                REFERENCES job_type (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION,
-            FOREIGN KEY (specification_id)
-                REFERENCES specification (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION
-        );
-        COMMIT;")
-}
+    DBQualityControl::drop_schema(&conn)?;
+    DBQualityControl::initialize(&conn)?;
 
-fn drop_database(conn: &Connection) -> Result<(), rusqlite::Error> {
-    conn.execute_batch(
-        "
-        BEGIN;
-        DROP TABLE IF EXISTS section;
-        DROP TABLE IF EXISTS additive_section;
-        DROP TABLE IF EXISTS job_type;
-        DROP TABLE IF EXISTS specification;
-        DROP TABLE IF EXISTS job_specification;
-        COMMIT;
-        "
-        )
+    Ok(())
 }
@@ -1,10 +1,17 @@
 use std::collections::HashMap;
+use std::error::Error;
 use plotters::prelude::*;
+use plotters::coord::Shift;
 
-use questionnaire::CSVWrite;
+use questionnaire::{CSVWrite, JSONWrite};
+use questionnaire::json::{self, SectionStatistics};
+use questionnaire::pdf::RasterImage;
+use questionnaire::export::ChartSource;
 
+#[derive(Default)]
 pub struct PlotData {
-    data: HashMap<String,(u64,u64)>
+    // (section id, section title) -> (ok, no). Sections counted by title only have no id.
+    data: HashMap<(Option<u64>,String),(u64,u64)>
 }
 
 impl PlotData {
@@ -15,30 +22,61 @@ impl PlotData {
     }
 
     pub fn increment_yes(&mut self, key: String) {
-        if let None = self.data.get(&key) {
-            self.data.insert(key.clone(), (0,0));
-        }
-        self.data.get_mut(&key).unwrap().0 += 1;
+        self.data.entry((None, key)).or_insert((0,0)).0 += 1;
     }
 
     pub fn increment_no(&mut self, key: String) {
-        if let None = self.data.get(&key) {
-            self.data.insert(key.clone(), (0,0));
+        self.data.entry((None, key)).or_insert((0,0)).1 += 1;
+    }
+
+    // Weighted counts, *earned* out of *possible*. More earned than possible counts as all earned.
+    pub fn add_score(&mut self, section_id: u64, title: String, earned: u64, possible: u64) {
+        let entry = self.data.entry((Some(section_id), title)).or_insert((0,0));
+        entry.0 += earned;
+        entry.1 += possible.saturating_sub(earned);
+    }
+
+    pub fn make_plot(&self, file_path: String) -> Result<(), Box<dyn Error>> {
+        let root = SVGBackend::new(&file_path, (1024,640)).into_drawing_area();
+
+        Self::create_plot(root, String::from("Compliance"), self.chart_data())
+    }
+
+    // Chart drawn in memory, to be embedded in a report.
+    pub fn render_chart(&self, dimensions: (u32,u32)) -> Result<RasterImage, Box<dyn Error>> {
+        let mut buffer = vec![0u8; (dimensions.0 * dimensions.1 * 3) as usize];
+
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, dimensions).into_drawing_area();
+            Self::create_plot(root, String::from("Compliance"), self.chart_data())?;
+        }
+
+        Ok(RasterImage::new(dimensions.0, dimensions.1, buffer))
+    }
+
+    // Chart as an SVG document, to be inlined in a web page.
+    pub fn render_svg(&self, dimensions: (u32,u32)) -> Result<String, Box<dyn Error>> {
+        let mut svg = String::new();
+
+        {
+            let root = SVGBackend::with_string(&mut svg, dimensions).into_drawing_area();
+            Self::create_plot(root, String::from("Compliance"), self.chart_data())?;
         }
-        self.data.get_mut(&key).unwrap().1 += 1;
+
+        Ok(svg)
     }
 
-    pub fn make_plot(&self) {
-        // build the data.
+    // (section, percentage), ordered by section.
+    fn chart_data(&self) -> Vec<(String,u64)> {
         let mut data = Vec::new();
-        for (k,v) in self.data.iter() {
+        for ((_, title),v) in self.data.iter() {
             let percent = Self::get_valid_percentage(*v);
 
-            data.push((k.clone(),percent));
+            data.push((title.clone(),percent));
         }
 
-
-        Self::create_plot(String::from("Testing"), (1024,640), String::from("Title"), data);
+        data.sort();
+        data
     }
 
     fn get_valid_percentage(tuple: (u64,u64)) -> u64 {
@@ -52,25 +90,28 @@ impl PlotData {
         (percent_float * 100.0) as u64
     }
 
-    fn create_plot(file_path: String, dimensions: (u32,u32), title: String, data: Vec<(String,u64)>) -> Result<(), Box<dyn std::error::Error>>{
-
-        let root = SVGBackend::new(&file_path, dimensions).into_drawing_area();
+    fn create_plot<DB>(root: DrawingArea<DB, Shift>, title: String, data: Vec<(String,u64)>) -> Result<(), Box<dyn Error>>
+        where DB: DrawingBackend, DB::ErrorType: 'static
+    {
+        if data.is_empty() {
+            return Err("No data to plot".into());
+        }
 
-        root.fill(&WHITE).unwrap();
+        root.fill(&WHITE)?;
         root.margin(10,10,10,10);
 
         // To avoid out-of-bounds, include - 1
         let max_y = data.len() - 1;
 
         let mut ctx = ChartBuilder::on(&root)
-            .caption(title, ("Arial", 30))
+            .caption(title, ("sans-serif", 30))
             .set_label_area_size(LabelAreaPosition::Left, 120)
             .set_label_area_size(LabelAreaPosition::Bottom,40)
             .build_cartesian_2d(0u64..100u64, (0..max_y).into_segmented())?;
 
         ctx.configure_mesh()
             .y_labels(max_y)
-            .y_label_style(("Arial",20))
+            .y_label_style(("sans-serif",20))
             .y_label_formatter(&|x| {
                 // Extract the y value
                 let x = match x {
@@ -80,12 +121,10 @@ impl PlotData {
                 };
 
                 // Map it to the section
-                let op_index = usize::try_from(*x);
-                let name = match op_index {
-                    Ok(v) => data[v].0.clone(),
-                    Err(_) => "".to_string()
-                };
-                format!("{}", name)
+                match data.get(*x) {
+                    Some(v) => v.0.clone(),
+                    None => "".to_string()
+                }
                         })
             .draw()?;
 
@@ -102,17 +141,33 @@ impl PlotData {
             bar
         }))?;
 
+        root.present()?;
+
         Ok(())
     }
 
+    // Compliance of every section, ordered by section.
+    pub fn statistics(&self) -> Vec<SectionStatistics> {
+        let mut statistics: Vec<SectionStatistics> = self.data.iter().map(|((section_id, title), (yes, no))| SectionStatistics {
+            section_id: *section_id,
+            section: title.to_string(),
+            ok: *yes,
+            no: *no,
+            percent_compliance: PlotData::get_valid_percentage((*yes, *no)),
+        }).collect();
+
+        statistics.sort_by(|a, b| (&a.section, a.section_id).cmp(&(&b.section, b.section_id)));
+        statistics
+    }
+
     fn to_record(&self) -> Vec<PlotDataRecord> {
         let mut records = Vec::new();
 
-        for (k, (yes, no)) in &self.data {
+        for ((_, title), (yes, no)) in &self.data {
             let percentage = PlotData::get_valid_percentage((*yes, *no));
 
             let new_record = PlotDataRecord {
-                section: k.to_string(),
+                section: title.to_string(),
                 ok: *yes,
                 no: *no,
                 percent_compliance: percentage
@@ -125,6 +180,20 @@ impl PlotData {
     }
 }
 
+impl ChartSource for PlotData {
+    fn raster(&self, dimensions: (u32,u32)) -> Result<RasterImage, Box<dyn Error>> {
+        self.render_chart(dimensions)
+    }
+
+    fn svg(&self, dimensions: (u32,u32)) -> Result<String, Box<dyn Error>> {
+        self.render_svg(dimensions)
+    }
+
+    fn statistics(&self) -> Vec<SectionStatistics> {
+        PlotData::statistics(self)
+    }
+}
+
 // For CSV writing
 #[derive(Debug,serde::Serialize)]
 struct PlotDataRecord {
@@ -150,3 +219,102 @@ impl CSVWrite for PlotData {
             
     }
 }
+
+// Same schema version as the questionnaire export, see *questionnaire::json*.
+#[derive(serde::Serialize)]
+struct PlotDataJson {
+    schema_version: u32,
+    sections: Vec<SectionStatisticsJson>,
+}
+
+#[derive(serde::Serialize)]
+struct SectionStatisticsJson {
+    section: String,
+    #[serde(flatten)]
+    statistics: SectionStatistics,
+}
+
+impl JSONWrite for PlotData {
+    fn write_json(&self, file_path: String) -> Result<(), Box<dyn std::error::Error>> {
+        let sections = self.statistics().into_iter()
+            .map(|statistics| SectionStatisticsJson { section: statistics.section.clone(), statistics })
+            .collect();
+
+        let json = serde_json::to_string_pretty(&PlotDataJson { schema_version: json::SCHEMA_VERSION, sections })?;
+        std::fs::write(file_path, json)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plot_data() -> PlotData {
+        let mut plot = PlotData::new();
+        plot.increment_yes(String::from("Valves"));
+        plot.increment_no(String::from("Valves"));
+        plot.add_score(1, String::from("Bolts"), 3, 4);
+        plot.add_score(0, String::from("Bolts"), 2, 2);
+        plot
+    }
+
+    #[test]
+    fn render_chart_size() {
+        let image = plot_data().render_chart((320, 200)).unwrap();
+
+        assert_eq!((320, 200), (image.width, image.height));
+        assert_eq!(320 * 200 * 3, image.rgb.len());
+    }
+
+    #[test]
+    fn render_svg() {
+        let svg = plot_data().render_svg((320, 200)).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Valves"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let plot = PlotData::new();
+
+        assert!(plot.render_chart((320, 200)).is_err());
+        assert!(plot.render_svg((320, 200)).is_err());
+    }
+
+    #[test]
+    fn statistics_order() {
+        let statistics = plot_data().statistics();
+
+        // By title, then by section id, sections counted by title first.
+        let order: Vec<(Option<u64>, &str)> = statistics.iter().map(|s| (s.section_id, s.section.as_str())).collect();
+        assert_eq!(vec![(Some(0), "Bolts"), (Some(1), "Bolts"), (None, "Valves")], order);
+        assert_eq!((3, 1, 75), (statistics[1].ok, statistics[1].no, statistics[1].percent_compliance));
+        assert_eq!((1, 1, 50), (statistics[2].ok, statistics[2].no, statistics[2].percent_compliance));
+    }
+
+    #[test]
+    fn add_score_over_possible() {
+        let mut plot = PlotData::new();
+        plot.add_score(0, String::from("Bolts"), 5, 4);
+
+        let statistics = plot.statistics();
+        assert_eq!((5, 0, 100), (statistics[0].ok, statistics[0].no, statistics[0].percent_compliance));
+    }
+
+    #[test]
+    fn write_json() {
+        let file_path = std::env::temp_dir().join("qcr_plot_data_test.json");
+
+        plot_data().write_json(file_path.to_string_lossy().to_string()).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file_path).unwrap()).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(json::SCHEMA_VERSION as u64, json["schema_version"].as_u64().unwrap());
+        let sections: Vec<&str> = json["sections"].as_array().unwrap().iter().map(|s| s["section"].as_str().unwrap()).collect();
+        assert_eq!(vec!["Bolts", "Bolts", "Valves"], sections);
+    }
+}
@@ -2,7 +2,11 @@ use gdnative::prelude::*;
 use gdnative::api::Resource;
 
 use sql_database::db_handler::DBQualityControlHandle;
-use sql_database::db::{JobType,Section, Specification};
+use sql_database::error::DbError;
+use questionnaire::job::{GENERAL_SECTION_ID, GENERAL_SECTION_TITLE};
+use questionnaire::questionnaire::QuestionStatus;
+use questionnaire::rules::RuleAction;
+use sql_database::db::{JobType,Section, Specification, ReportSummary, ResponseScale, Measurement, SpecificationRule};
 
 /* Convert the data obtained from sql_database::db,
  * and have it ready for godot consumption
@@ -35,6 +39,7 @@ impl ConvertTo<SectionData> for Section {
 impl ConvertTo<SepecificationData> for Specification {
     fn convert(&self) -> SepecificationData {
 
+        // Specifications without a section are shown under the general section, like in reports.
         let section = {
             match &self.section {
                 Some(s) => {
@@ -45,7 +50,12 @@ impl ConvertTo<SepecificationData> for Specification {
                         }
                         )
                 },
-                None => None
+                None => Some(
+                        SectionData {
+                            section_id: GENERAL_SECTION_ID,
+                            section_name: GENERAL_SECTION_TITLE.to_string()
+                        }
+                    )
             }
         };
 
@@ -57,6 +67,72 @@ impl ConvertTo<SepecificationData> for Specification {
     }
 }
 
+impl ConvertTo<ReportData> for ReportSummary {
+    fn convert(&self) -> ReportData {
+        ReportData {
+            report_id: self.id,
+            job_name: self.job_name.clone(),
+            template_revision: self.template_revision,
+            engineer: self.header.engineer.clone(),
+            job: self.header.job.clone(),
+            day: self.header.day,
+            month: self.header.month,
+            year: self.header.year,
+            score: self.score.as_ref().map(|score| score.score),
+            verdict: self.score.as_ref().and_then(|score| score.verdict.clone()),
+            review_state: self.review.state.clone(),
+            reviewer: self.review.reviewer.clone(),
+        }
+    }
+}
+
+impl ConvertTo<ResponseScaleData> for ResponseScale {
+    fn convert(&self) -> ResponseScaleData {
+        let options = self.options.iter().map(|option| {
+            ResponseOptionData {
+                option_id: option.id,
+                code: option.code.clone(),
+                label: option.label.clone(),
+                status: option.status.clone()
+            }
+        }).collect();
+
+        ResponseScaleData {
+            scale_id: self.id,
+            scale_name: self.scale_name.clone(),
+            options
+        }
+    }
+}
+
+impl ConvertTo<RuleData> for SpecificationRule {
+    fn convert(&self) -> RuleData {
+        RuleData {
+            rule_id: self.id,
+            target_specification_id: self.target_specification_id,
+            target_section_id: self.target_section_id,
+            depends_on_specification_id: self.depends_on_specification_id,
+            status: self.status.clone(),
+            action: self.action.clone()
+        }
+    }
+}
+
+// Logs the error of *result*, and keeps its message in *last_error* for the GUI.
+pub fn record_error<T>(last_error: &mut String, result: Result<T, DbError>) -> Option<T> {
+    match result {
+        Ok(value) => {
+            last_error.clear();
+            Some(value)
+        },
+        Err(e) => {
+            godot_error!("{}", e);
+            *last_error = e.to_string();
+            None
+        }
+    }
+}
+
 /* Storing data to be use in GDScript */
 #[derive(NativeClass,ToVariant)]
 #[inherit(Resource)]
@@ -84,22 +160,94 @@ pub struct SepecificationData {
     section: Option<SectionData>,
 }
 
+#[derive(NativeClass,ToVariant)]
+#[inherit(Resource)]
+#[no_constructor]
+pub struct ReportData {
+    report_id: u64,
+    job_name: String,
+    template_revision: Option<u64>,
+    engineer: String,
+    job: String,
+    day: i64,
+    month: i64,
+    year: i64,
+    score: Option<f64>,
+    verdict: Option<String>,
+    // draft, submitted, approved or returned.
+    review_state: String,
+    reviewer: Option<String>,
+}
+
+#[derive(NativeClass,ToVariant)]
+#[inherit(Resource)]
+#[no_constructor]
+pub struct ResponseOptionData {
+    option_id: u64,
+    code: String,
+    label: String,
+    // Outcome of the option: OK, NO or N/A.
+    status: String,
+}
+
+#[derive(NativeClass,ToVariant)]
+#[inherit(Resource)]
+#[no_constructor]
+pub struct ResponseScaleData {
+    scale_id: u64,
+    scale_name: String,
+    options: Vec<ResponseOptionData>,
+}
+
+#[derive(NativeClass,ToVariant)]
+#[inherit(Resource)]
+#[no_constructor]
+pub struct RuleData {
+    rule_id: u64,
+    target_specification_id: Option<u64>,
+    target_section_id: Option<u64>,
+    depends_on_specification_id: u64,
+    status: String,
+    // show_if or hide_if
+    action: String,
+}
+
 #[derive(NativeClass)]
 #[inherit(Resource)]
 pub struct DatabaseAPI {
-    db_handle: DBQualityControlHandle
+    db_handle: DBQualityControlHandle,
+    // Message of the last failed call, empty when it succeeded.
+    last_error: String,
 }
 
 #[methods]
 impl DatabaseAPI {
     fn new(_owner: &Resource) -> Self {
-        Self { db_handle: DBQualityControlHandle::new("database/qcr_database.db".to_string()) }
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        // Older databases are upgraded in place.
+        if let Err(e) = db_handle.initialize() {
+            godot_error!("Unable to upgrade database schema: {}", e);
+        }
+
+        Self { db_handle, last_error: String::new() }
+    }
+
+    // Shown to the user, e.g. "A section named "Cover Page" already exists".
+    #[method]
+    pub fn get_last_error(&self) -> String {
+        self.last_error.clone()
+    }
+
+    fn check<T>(&mut self, result: Result<T, DbError>) -> Option<T> {
+        record_error(&mut self.last_error, result)
     }
 
 
     #[method]
     pub fn get_all_job_header_info(&mut self) -> Vec<JobHeaderData> {
-        let job_types: Vec<JobType> = self.db_handle.get_all_job_types().unwrap();
+        let result = self.db_handle.get_all_job_types();
+        let job_types: Vec<JobType> = self.check(result).unwrap_or_default();
 
         let mut job_info = Vec::new();
 
@@ -111,7 +259,7 @@ impl DatabaseAPI {
         job_info
     }
 
-    fn all_data<T: ConvertTo<U>,U, F: FnMut() -> Vec<T>>(mut f: F) -> Vec<U> {
+    fn all_data<T: ConvertTo<U>,U, F: FnOnce() -> Vec<T>>(f: F) -> Vec<U> {
         // Store all values obtained from function *f*.
         let storage = f();
 
@@ -132,29 +280,188 @@ impl DatabaseAPI {
     
     #[method]
     pub fn get_all_section_data(&mut self) -> Vec<SectionData> {
-        DatabaseAPI::all_data(|| self.db_handle.get_all_sections().unwrap())
+        let result = self.db_handle.get_all_sections();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
+    }
+
+    #[method]
+    pub fn get_all_additive_section_data(&mut self) -> Vec<SectionData> {
+        let result = self.db_handle.get_all_additive_sections();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
+    }
+
+    #[method]
+    pub fn add_additive_section(&mut self, section_name: String) {
+        let result = self.db_handle.add_additive_section(section_name);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn remove_additive_section(&mut self, section_id: u64) {
+        let result = self.db_handle.remove_additive_section(section_id);
+        self.check(result);
     }
 
     #[method]
     pub fn get_all_specification_data(&mut self) -> Vec<SepecificationData> {
-        DatabaseAPI::all_data(|| self.db_handle.get_all_specifications().unwrap())
+        let result = self.db_handle.get_all_specifications();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
     }
 
     #[method]
-    pub fn update_section(&mut self, id: u64, content: String) {
-        if let Err(_) = self.db_handle.update_section(id, content){
-            godot_error!("Failed to update section:");
+    pub fn get_all_report_data(&mut self) -> Vec<ReportData> {
+        let result = self.db_handle.get_all_reports();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
+    }
+
+    #[method]
+    pub fn remove_report(&mut self, report_id: u64) {
+        let result = self.db_handle.remove_report(report_id);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn get_all_response_scale_data(&mut self) -> Vec<ResponseScaleData> {
+        let result = self.db_handle.get_all_response_scales();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
+    }
+
+    #[method]
+    pub fn add_response_scale(&mut self, scale_name: String) {
+        let result = self.db_handle.add_response_scale(scale_name);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn remove_response_scale(&mut self, scale_id: u64) {
+        let result = self.db_handle.remove_response_scale(scale_id);
+        self.check(result);
+    }
+
+    // *status* is the outcome the option counts as: OK, NO or N/A.
+    #[method]
+    pub fn add_response_option(&mut self, scale_id: u64, code: String, label: String, status: String) {
+        match status.parse::<QuestionStatus>() {
+            Ok(QuestionStatus::Unanswered) | Err(_) => {
+                godot_error!("Invalid outcome for response option: {}", status);
+                self.last_error = format!("Invalid outcome for response option: {}", status);
+                return;
+            },
+            Ok(_) => {}
         }
+
+        let result = self.db_handle.add_response_option(scale_id, code, label, status);
+        self.check(result);
     }
 
     #[method]
-    pub fn update_specification(&mut self, spec_id: u64, spec_content: String, section_id: u64){
-        if let Err(_) = self.db_handle.update_specification_content(spec_id, spec_content) {
-            godot_error!("Unable to update specification");
+    pub fn remove_response_option(&mut self, option_id: u64) {
+        let result = self.db_handle.remove_response_option(option_id);
+        self.check(result);
+    }
+
+    // Null goes back to the default OK / N/A / NO scale.
+    #[method]
+    pub fn set_job_type_response_scale(&mut self, job_type_id: u64, scale_id: Option<u64>) {
+        let result = self.db_handle.set_job_type_response_scale(job_type_id, scale_id);
+        self.check(result);
+    }
+
+    // Null makes the specification use the scale of its job type.
+    #[method]
+    pub fn set_specification_response_scale(&mut self, specification_id: u64, scale_id: Option<u64>) {
+        let result = self.db_handle.set_specification_response_scale(specification_id, scale_id);
+        self.check(result);
+    }
+
+    // Null limits are not checked.
+    #[method]
+    pub fn set_specification_measurement(&mut self, specification_id: u64, unit: String, nominal_value: f64, min_value: Option<f64>, max_value: Option<f64>) {
+        let measurement = Measurement::new(unit, nominal_value, min_value, max_value);
+
+        let result = self.db_handle.set_specification_measurement(specification_id, Some(&measurement));
+        self.check(result);
+    }
+
+    // The specification is answered like a checklist item again.
+    #[method]
+    pub fn remove_specification_measurement(&mut self, specification_id: u64) {
+        let result = self.db_handle.set_specification_measurement(specification_id, None);
+        self.check(result);
+    }
+
+    // A weight of 0 leaves the specification out of the score.
+    #[method]
+    pub fn set_specification_weight(&mut self, specification_id: u64, weight: u32) {
+        let result = self.db_handle.set_specification_weight(specification_id, weight);
+        self.check(result);
+    }
+
+    // Minimum score in percent, null for reports that are not judged.
+    #[method]
+    pub fn set_job_type_pass_threshold(&mut self, job_type_id: u64, pass_threshold: Option<f64>) {
+        let result = self.db_handle.set_job_type_pass_threshold(job_type_id, pass_threshold);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn get_all_rule_data(&mut self) -> Vec<RuleData> {
+        let result = self.db_handle.get_all_specification_rules();
+        DatabaseAPI::all_data(|| self.check(result).unwrap_or_default())
+    }
+
+    // Either a specification or a section is targeted. *action* is show_if or hide_if.
+    #[method]
+    pub fn add_specification_rule(&mut self, target_specification_id: Option<u64>, target_section_id: Option<u64>, depends_on_specification_id: u64, status: String, action: String) {
+        if let Err(e) = status.parse::<QuestionStatus>() {
+            godot_error!("{}", e);
+            self.last_error = e.to_string();
+            return;
         }
-        if let Err(_) = self.db_handle.update_specification_section(spec_id, section_id) {
-            godot_error!("Unable to update specification section");
+
+        if let Err(e) = action.parse::<RuleAction>() {
+            godot_error!("{}", e);
+            self.last_error = e.to_string();
+            return;
         }
+
+        let rule = SpecificationRule {
+            id: 0,
+            target_specification_id,
+            target_section_id,
+            depends_on_specification_id,
+            status,
+            action
+        };
+
+        let result = self.db_handle.add_specification_rule(&rule);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn remove_specification_rule(&mut self, rule_id: u64) {
+        let result = self.db_handle.remove_specification_rule(rule_id);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn update_section(&mut self, id: u64, content: String) {
+        let result = self.db_handle.update_section(id, content);
+        self.check(result);
+    }
+
+    #[method]
+    pub fn update_specification(&mut self, spec_id: u64, spec_content: String, section_id: u64){
+        let content = self.db_handle.update_specification_content(spec_id, spec_content);
+        // The general section is not stored, it means the specification has no section.
+        let section = if section_id == GENERAL_SECTION_ID {
+            self.db_handle.remove_specification_section(spec_id)
+        } else {
+            self.db_handle.update_specification_section(spec_id, section_id)
+        };
+
+        // The first failure is shown.
+        self.check(content.and(section));
     }
     /*
 
@@ -0,0 +1,21 @@
+/*
+ * Formats a QCReport can be exported as.
+ *
+ * In-house formats are added to *in_house_exporters*, the Godot side lists
+ * them with *QCReport::export_formats* and exports with *export_report*.
+ */
+use questionnaire::export::{ExporterRegistry, ReportExporter};
+
+// E.g. vec![Box::new(InHouseExporter)]. A format with a built-in name replaces the built-in exporter.
+fn in_house_exporters() -> Vec<Box<dyn ReportExporter>> {
+    vec![]
+}
+
+// The built-in formats, then the in-house ones.
+pub fn report_exporters() -> ExporterRegistry {
+    let mut registry = ExporterRegistry::default();
+    for exporter in in_house_exporters() {
+        registry.register(exporter);
+    }
+    registry
+}
@@ -2,87 +2,100 @@ use gdnative::prelude::*;
 use gdnative::api::Resource;
 
 use sql_database::db_handler::DBQualityControlHandle;
+use sql_database::error::DbError;
 use sql_database::db::{JobType,Section, Specification};
 
-use crate::database_api::{JobHeaderData, ConvertTo};
+use crate::database_api::{JobHeaderData, ConvertTo, record_error};
 
 #[derive(NativeClass)]
 #[inherit(Resource)]
 pub struct JobDatabaseAPI {
-    db_handle: DBQualityControlHandle
+    db_handle: DBQualityControlHandle,
+    // Message of the last failed call, empty when it succeeded.
+    last_error: String,
 }
 
 #[methods]
 impl JobDatabaseAPI {
     fn new(_owner: &Resource) -> Self {
-        Self { db_handle: DBQualityControlHandle::new("database/qcr_database.db".to_string()) }
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        // Older databases are upgraded in place.
+        if let Err(e) = db_handle.initialize() {
+            godot_error!("Unable to upgrade database schema: {}", e);
+        }
+
+        Self { db_handle, last_error: String::new() }
+    }
+
+    // Shown to the user, e.g. "A job type named "Cement" already exists".
+    #[method]
+    pub fn get_last_error(&self) -> String {
+        self.last_error.clone()
+    }
+
+    fn check<T>(&mut self, result: Result<T, DbError>) -> Option<T> {
+        record_error(&mut self.last_error, result)
     }
 
     #[method]
     pub fn job_has_specification(&mut self, job_id: u64, specification_id: u64) -> bool {
         let result = self.db_handle.job_has_specification(job_id, specification_id);
 
-        match result {
-            Ok(b) => b, // can be true or false.
-            Err(_) => false // error? definitely false.
-        }
+        // error? definitely false.
+        self.check(result).unwrap_or(false)
     }
 
     #[method]
     pub fn job_exists(&mut self, job_type_name: String) -> bool {
         let result = self.db_handle.job_exists(job_type_name);
 
-        match result {
-            Ok(b) => b,
-            Err(_) => false
-        }
+        self.check(result).unwrap_or(false)
     }
 
     #[method]
     pub fn get_job_type_id(&mut self, job_type_name: String) -> Option<u64> {
         let result = self.db_handle.get_job_type_id(job_type_name);
 
-        match result {
-            Ok(val) => Some(val),
-            Err(_)  => None
-        }
+        self.check(result)
     }
 
     #[method]
     pub fn get_job_type(&mut self, job_type_id: u64) -> Option<JobHeaderData> {
         let result = self.db_handle.get_job_type(job_type_id);
 
-        match result {
-            Ok(job) => Some(job.convert()),
-            Err(_)  => None
-        }
+        self.check(result).map(|job| job.convert())
+    }
+
+    // Reports store the revision they were answered against, compare with this to know if the template changed since.
+    #[method]
+    pub fn get_job_template_revision(&mut self, job_type_id: u64) -> Option<u64> {
+        let result = self.db_handle.get_job_template_revision(job_type_id);
+
+        self.check(result)
     }
 
     #[method]
     pub fn add_job_specification(&mut self, job_type_id: u64, specification_id: u64) {
-        let _result = self.db_handle.add_job_specification(job_type_id, specification_id);
+        let result = self.db_handle.add_job_specification(job_type_id, specification_id);
+        self.check(result);
     }
 
     #[method]
     pub fn remove_job_specification(&mut self, job_type_id:u64, specification_id:u64) {
-        let _result = self.db_handle.remove_job_spec(job_type_id, specification_id);
+        let result = self.db_handle.remove_job_spec(job_type_id, specification_id);
+        self.check(result);
     }
 
     #[method]
     pub fn add_job_type(&mut self, job_type_name: String) {
         let result = self.db_handle.add_job_type(job_type_name);
-
-        if let Err(_) = result  {
-            godot_error!("Unable to add job type");
-        }
+        self.check(result);
     }
 
     #[method]
     pub fn update_job_type_name(&mut self, job_type_id: u64, new_name: String) {
         let result = self.db_handle.update_job_type(job_type_id, new_name);
-
-        if let Err(_) = result {
-            godot_error!("Unable to update job name");
-        }
+        self.check(result);
     }
 }
@@ -8,6 +8,7 @@ mod questionnaire_data;
 mod utils;
 mod database_api;
 mod job_database_api;
+mod exporters;
 
 use questionnaire_data::QuestionnaireData;
 use qcreport::QCReport;
@@ -9,19 +9,36 @@ use gdnative::api::Resource;
 
 use questionnaire::questionnaire::Questionnaire;
 //use crate::questionnaire_data::QuestionnaireData;
-use questionnaire::questionnaire::{UnitForm, QuestionStatus, PDFable};
-use questionnaire::data::{Id, Question, Section};
+use questionnaire::questionnaire::{UnitForm, QuestionStatus};
+use questionnaire::data::{Id, Question, Section, QuestionKind, Measurement};
 use questionnaire::job::Job;
+use questionnaire::document::{self, ReportHeader};
+use questionnaire::scale::{ResponseOption, ResponseScale};
+use questionnaire::rules::{Rule, RuleAction, RuleTarget};
+use questionnaire::attachment::{Attachment, AttachmentStore};
+use questionnaire::history::{FormChange, FormField};
+use questionnaire::review::{Review, ReviewState};
+use questionnaire::diff::QuestionnaireDiff;
+use questionnaire::error::QuestionnaireError;
+use questionnaire::pdf::ReportMetadata;
+use questionnaire::export::{ExportOptions, ExportSource, ExporterRegistry};
 
 use sql_database::db_handler::DBQualityControlHandle;
+use sql_database::error::{DbError, Entity, Key};
 
 use std::collections::HashMap;
-use sql_database::db::{JobSpecificationSection};
+use sql_database::db::{JobSpecificationSection, ReportRecord, ReportUnitForm};
 use sql_database::*;
 use plotting::PlotData;
 
+use crate::exporters::report_exporters;
+
 use questionnaire::CSVWrite;
 
+// Files attached to forms, named after their content.
+const ATTACHMENT_DIRECTORY: &str = "database/attachments";
+// Size of the chart embedded in reports, in pixels.
+const CHART_SIZE: (u32, u32) = (1024, 640);
 
 struct GDQuestionStatus(QuestionStatus);
 
@@ -30,7 +47,8 @@ impl ToVariant for GDQuestionStatus {
         match self.0 {
             QuestionStatus::OK => 0.to_variant(),
             QuestionStatus::NO => 1.to_variant(),
-            QuestionStatus::NA => 2.to_variant()
+            QuestionStatus::NA => 2.to_variant(),
+            QuestionStatus::Unanswered => 3.to_variant()
         }
     }
 }
@@ -43,14 +61,47 @@ impl FromVariant for GDQuestionStatus {
             0 => Ok(GDQuestionStatus(QuestionStatus::OK)),
             1 => Ok(GDQuestionStatus(QuestionStatus::NO)),
             2 => Ok(GDQuestionStatus(QuestionStatus::NA)),
+            3 => Ok(GDQuestionStatus(QuestionStatus::Unanswered)),
             _ => Err(FromVariantError::UnknownEnumVariant {
                 variant: "i64".to_owned(),
-                expected: &["0","1","2"],
+                expected: &["0","1","2","3"],
             }),
         }
     }
 }
 
+// Why the run-time data of a report could not be built from the database.
+#[derive(Debug)]
+enum AcquireError {
+    Database(DbError),
+    // Stored text that does not name a value, e.g. an unknown status.
+    Data(QuestionnaireError),
+    // Id of a specification rule without a single target.
+    RuleTarget(u64),
+}
+
+impl std::fmt::Display for AcquireError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcquireError::Database(e) => write!(f, "{}", e),
+            AcquireError::Data(e) => write!(f, "{}", e),
+            AcquireError::RuleTarget(id) => write!(f, "Specification rule {} does not have a single target", id),
+        }
+    }
+}
+
+impl From<DbError> for AcquireError {
+    fn from(e: DbError) -> Self {
+        AcquireError::Database(e)
+    }
+}
+
+impl From<QuestionnaireError> for AcquireError {
+    fn from(e: QuestionnaireError) -> Self {
+        AcquireError::Data(e)
+    }
+}
+
 /* Input generate from user
  *
  * Godot Input -> JobQuery -> rust
@@ -72,14 +123,35 @@ struct HeaderInfo {
     year: i64,
 }
 
+impl HeaderInfo {
+    fn to_report_header(&self) -> ReportHeader {
+        ReportHeader::new(self.engineer.clone(), self.job.clone(), self.day, self.month, self.year)
+    }
+
+    fn from_report_header(header: ReportHeader) -> Self {
+        HeaderInfo {
+            engineer: header.engineer,
+            job: header.job,
+            day: header.day,
+            month: header.month,
+            year: header.year,
+        }
+    }
+}
+
 // Contains the data for the current instance of the report.
 #[derive(NativeClass)]
 #[inherit(Resource)]
 pub struct QCReport {
     pub questionnaire_data: Option<Questionnaire>,
     header_info: Option<HeaderInfo>,
-    plot_data: Option<PlotData>
-
+    plot_data: Option<PlotData>,
+    // Set once the report is stored in, or loaded from, the database.
+    report_id: Option<u64>,
+    // Formats the report can be exported as, see exporters.rs.
+    exporters: ExporterRegistry,
+    // Why the last report generation failed, empty if it succeeded.
+    last_error: String,
 }
 
 #[methods]
@@ -88,18 +160,63 @@ impl QCReport {
 
         QCReport { questionnaire_data: None::<Questionnaire>,
         header_info: None::<HeaderInfo>,
-        plot_data: None::<PlotData> }
+        plot_data: None::<PlotData>,
+        report_id: None,
+        exporters: report_exporters(),
+        last_error: String::new() }
     }
 
+    // The forms are written by the "csv" exporter, the plot next to them.
     #[method]
-    fn write_csv(&self, form_file_path: String, plot_file_path: String) {
-        match (&self.questionnaire_data, &self.plot_data) {
-            (None, None) => godot_error!("No data to write"),
-            (_, None) => godot_error!("No plot data"),
-            (None, _) => godot_error!("No questionnaire data"),
-            (Some(q), Some(plot)) => {
-                q.write_csv(form_file_path);
-                plot.write_csv(plot_file_path);
+    fn write_csv(&self, form_file_path: String, plot_file_path: String) -> bool {
+        let plot = match &self.plot_data {
+            Some(plot) => plot,
+            None => {
+                godot_error!("No plot data");
+                return false;
+            }
+        };
+
+        if !self.export("csv", form_file_path, String::new(), ExportOptions::default()) {
+            return false;
+        }
+
+        match plot.write_csv(plot_file_path.clone()) {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("Failed to write {}: {}", plot_file_path, e);
+                false
+            }
+        }
+    }
+
+    // Machine-readable results, see *questionnaire::json* for the schema.
+    #[method]
+    fn write_json(&self, file_path: String) -> bool {
+        self.export("json", file_path, String::new(), ExportOptions::default())
+    }
+
+    // Applies the statuses and notes of a questionnaire CSV filled in a spreadsheet.
+    // Returns the rows that were not imported, e.g. "line 4: Unknown status for the specification",
+    // or the error when nothing could be imported.
+    #[method]
+    fn import_csv(&mut self, file_path: String) -> Vec<String> {
+        let q = match &mut self.questionnaire_data {
+            Some(q) => q,
+            None => {
+                godot_error!("No questionnaire data");
+                return vec![String::from("No questionnaire data")];
+            }
+        };
+
+        match q.import_csv(file_path.clone()) {
+            Ok(report) => {
+                godot_print!("Imported {} rows from {}", report.applied, file_path);
+                report.unmatched.iter().chain(report.invalid.iter()).map(|issue| issue.to_string()).collect()
+            },
+            Err(e) => {
+                godot_error!("Failed to import {}: {}", file_path, e);
+                vec![e.to_string()]
             }
         }
     }
@@ -109,8 +226,10 @@ impl QCReport {
         let result = self.acquire_questionnaire_data(query);
 
         match result {
-            Ok(ques) => {
+            Ok(mut ques) => {
+                ques.set_engineer(header_info.engineer.clone());
                 self.header_info = Some(header_info);
+                self.report_id = None;
                 //godot_print!("Questionnaire: {:?}", &ques);
                 self.questionnaire_data = Some(ques); 
             },
@@ -119,78 +238,611 @@ impl QCReport {
         }
     }
 
+    // Stores the report being filled, so that it can be resumed later.
     #[method]
-    fn build_plot(&mut self) {
-        match &self.questionnaire_data {
-            Some(qs) => {
-                let mut plot_data = PlotData::new();
+    fn save_progress(&self, file_path: String) -> bool {
+        match (&self.questionnaire_data, &self.header_info) {
+            (Some(q), Some(header)) => {
+                if let Err(e) = document::save_questionnaire(file_path, &header.to_report_header(), q) {
+                    godot_error!("Failed to save report: {}", e);
+                    false
+                } else {
+                    true
+                }
+            },
+            _ => {
+                godot_error!("No report to save");
+                false
+            }
+        }
+    }
+
+    #[method]
+    fn load_progress(&mut self, file_path: String) -> bool {
+        match document::load_questionnaire(file_path) {
+            Ok((header, ques)) => {
+                self.header_info = Some(HeaderInfo::from_report_header(header));
+                self.questionnaire_data = Some(ques);
+                // Plot belonged to the previous report.
+                self.plot_data = None;
+                self.report_id = None;
+                true
+            },
+            Err(e) => {
+                godot_error!("Failed to load report: {}", e);
+                false
+            }
+        }
+    }
+
+    // Stores the finished report in the database, or updates it if it was stored or loaded before.
+    // Returns the id of the stored report.
+    #[method]
+    fn save_to_database(&mut self) -> Option<u64> {
+        match (&self.questionnaire_data, &self.header_info) {
+            (Some(q), Some(header)) => {
+                // Same rule as for generating the report, a stored report is a finished one.
+                if !q.is_complete() {
+                    godot_error!("Unable to store report: {} unanswered specifications", q.completeness().unanswered);
+                    return None;
+                }
+
+                // Store a copy of the template text alongside each answer.
+                // Hidden forms are kept with the rules, editing the report can show them again.
+                let mut forms: Vec<ReportUnitForm> = Vec::new();
+                for (id, form) in q.all_forms() {
+                    let section = q.get_section(form.get_section_id());
+                    let question = q.get_question(form.get_section_id(), form.get_question_id());
+
+                    match (section, question) {
+                        (Ok(section), Ok(question)) => {
+                            let mut report_form = ReportUnitForm::new(*id,
+                                                                      form.get_section_id().primitive(),
+                                                                      section.get_title(),
+                                                                      form.get_question_id().primitive(),
+                                                                      question.get_title(),
+                                                                      form.get_status().to_string(),
+                                                                      form.get_notes())
+                                                                 .with_weight(question.get_weight());
+
+                            if let Some(response) = form.get_response() {
+                                report_form = report_form.with_response(response.code.clone(), response.label.clone());
+                            }
+
+                            if let Some(m) = question.get_measurement() {
+                                let measurement = db::Measurement::new(m.unit.clone(), m.nominal, m.min, m.max);
+                                report_form = report_form.with_measurement(measurement, form.get_value());
+                            }
+
+                            let attachments = form.get_attachments().iter().map(|a| {
+                                db::ReportAttachment::new(a.hash.clone(), a.file_name.clone(), a.media_type.clone())
+                            }).collect();
+                            report_form = report_form.with_attachments(attachments);
+
+                            // The options offered are kept, the scales of the templates can change afterwards.
+                            if let Ok(scale) = q.form_scale(*id) {
+                                report_form = report_form.with_scale(QCReport::to_db_scale(scale));
+                            }
+
+                            forms.push(report_form);
+                        },
+                        _ => {
+                            godot_error!("Form {} has no matching specification", id);
+                            return None;
+                        }
+                    }
+                }
 
                 let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
-                // Build data for plot
-                for (form_id, form) in qs.all_forms() {
-                    let section_id = form.get_section_id();
-                    let section: db::Section = db_handle.get_section(section_id.primitive()).unwrap();
-
-                    godot_print!("From status from section {}: {}", section.section_name, form.get_status());
-                    
-                    match form.get_status() {
-                        QuestionStatus::OK => plot_data.increment_yes(section.section_name),
-                        QuestionStatus::NO => plot_data.increment_no(section.section_name),
-                        _ => {},
+
+                let job_type_id = q.get_job().get_id().primitive();
+
+                // Reports with nothing to score are stored without a score.
+                let score = q.score().overall.percentage().map(|percentage| {
+                    db::ReportScore::new(percentage,
+                                         q.get_job().get_pass_threshold(),
+                                         q.verdict().map(|verdict| verdict.to_string()))
+                });
+
+                let history: Vec<db::ReportFormChange> = q.history().iter().map(|change| {
+                    db::ReportFormChange::new(change.form_id, change.timestamp, change.engineer.clone(),
+                                              change.field.to_string(), change.old_value.clone(), change.new_value.clone())
+                }).collect();
+
+                let rules: Vec<db::SpecificationRule> = q.get_job().get_rules().iter().map(QCReport::to_db_rule).collect();
+
+                let report_header = header.to_report_header();
+                let review = QCReport::to_db_review(q.review());
+                let record = ReportRecord { header: &report_header, forms: &forms, rules: &rules, score: score.as_ref(), history: &history, review: &review };
+
+                // The report, its rules, score, history and review are stored together, or not at all.
+                let stored = match self.report_id {
+                    Some(report_id) => db_handle.update_report(report_id, &record).map(|_| report_id),
+                    None => db_handle.save_report(Some(job_type_id), &record),
+                };
+
+                let report_id = match stored {
+                    Ok(report_id) => report_id,
+                    Err(e) => {
+                        godot_error!("Failed to store report: {}", e);
+                        return None;
                     }
+                };
+
+                self.report_id = Some(report_id);
+                Some(report_id)
+            },
+            _ => {
+                godot_error!("No report to store");
+                None
+            }
+        }
+    }
+
+    #[method]
+    fn load_from_database(&mut self, report_id: u64) -> bool {
+        match QCReport::read_report(report_id) {
+            Some((questionnaire, header)) => {
+                self.questionnaire_data = Some(questionnaire);
+                self.header_info = Some(HeaderInfo::from_report_header(header));
+                self.plot_data = None;
+                self.report_id = Some(report_id);
+                true
+            },
+            None => false
+        }
+    }
+
+    // Rebuilds the questionnaire of a stored report.
+    fn read_report(report_id: u64) -> Option<(Questionnaire, ReportHeader)> {
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        let report = match db_handle.get_report(report_id) {
+            Ok(report) => report,
+            Err(e) => {
+                godot_error!("{}", e);
+                return None;
+            }
+        };
+
+        // Rebuild the job from the snapshot stored with the report, not the current template.
+        // The job type could have been removed since, in which case it has no id anymore.
+        let job_type_id = report.job_type_id.unwrap_or(0);
+
+        let job_specs: Vec<JobSpecificationSection> = report.forms.iter().map(|form| {
+            JobSpecificationSection::new(job_type_id,
+                                         report.job_name.clone(),
+                                         Some(db::Section::new(form.section_id, form.section_name.clone())),
+                                         form.specification_id,
+                                         form.specification_content.clone())
+        }).collect();
+
+        let mut job = match QCReport::job_query_to_job_questionnaire(job_specs) {
+            Ok(mut jobs_hash) => match jobs_hash.remove(&job_type_id) {
+                Some(job) => job,
+                // A report without forms.
+                None => Job::new(job_type_id, report.job_name.clone(), String::from(""), HashMap::new()),
+            },
+            Err(e) => {
+                godot_error!("Failed to convert report to run-time data: {}", e);
+                return None;
+            }
+        };
+
+        // Skip logic the report was answered with, not the one of the current template.
+        for db_rule in report.rules.iter() {
+            match QCReport::to_rule(db_rule) {
+                Ok(rule) => job.add_rule(rule),
+                Err(e) => {
+                    godot_error!("{}", e);
+                    return None;
+                }
+            }
+        }
+
+        // Score with the weights and threshold stored with the report.
+        job.set_pass_threshold(report.score.as_ref().and_then(|score| score.pass_threshold));
+
+        // Measurements are checked against the limits, and answered with the scales, stored with the report.
+        for form in report.forms.iter() {
+            job.set_question_weight(&Id::<Question>::new(form.specification_id), form.weight);
+
+            if let Some(db_scale) = &form.scale {
+                match QCReport::to_response_scale(db_scale.clone()) {
+                    Ok(scale) => job.set_question_scale(&Id::<Question>::new(form.specification_id), Some(scale)),
+                    Err(e) => godot_error!("{}", e),
+                }
+            }
+
+            if let Some(m) = &form.measurement {
+                let measurement = Measurement::new(m.unit.clone(), m.nominal_value, m.min_value, m.max_value);
+                job.set_question_kind(&Id::<Question>::new(form.specification_id), QuestionKind::Measurement(measurement));
+            }
+        }
+
+        let mut forms = Vec::new();
+        for form in report.forms {
+            let status = match form.status.parse::<QuestionStatus>() {
+                Ok(status) => status,
+                Err(e) => {
+                    godot_error!("{}", e);
+                    return None;
+                }
+            };
+
+            let mut unit_form = UnitForm::new(form.form_id, Id::<Question>::new(form.specification_id), Id::<Section>::new(form.section_id), status, form.notes);
+
+            // The chosen option is kept even if the scale changed since.
+            if let (Some(code), Some(label)) = (form.response_code, form.response_label) {
+                unit_form.update_response(ResponseOption::new(code, label, status));
+            }
 
+            unit_form.update_value(form.value);
+
+            for a in form.attachments {
+                unit_form.add_attachment(Attachment::new(a.hash, a.file_name, a.media_type));
+            }
+
+            forms.push(unit_form);
+        }
+
+        let mut history = Vec::new();
+        match db_handle.get_report_history(report_id) {
+            Ok(changes) => {
+                for change in changes {
+                    match change.field.parse::<FormField>() {
+                        Ok(field) => history.push(FormChange::new(change.form_id, change.timestamp, change.engineer, field, change.old_value, change.new_value)),
+                        Err(e) => godot_error!("{}", e),
+                    }
                 }
+            },
+            Err(e) => godot_error!("Failed to load history of report {}: {}", report_id, e),
+        }
+
+        let mut questionnaire = Questionnaire::from_forms(job, forms);
+        questionnaire.set_history(history);
+        questionnaire.set_engineer(report.header.engineer.clone());
+
+        match report.review.state.parse::<ReviewState>() {
+            Ok(state) => questionnaire.set_review(Review::new(state, report.review.reviewer, report.review.comments)),
+            Err(e) => godot_error!("{}", e),
+        }
+
+        Some((questionnaire, report.header))
+    }
+
+    // Changes from the stored report *report_id* to the current report,
+    // as (section, specification, kind, old value, new value). Kind is one of added, removed, status or notes.
+    #[method]
+    fn diff_with_report(&self, report_id: u64) -> Vec<(String, String, String, String, String)> {
+        match self.diff_against(report_id) {
+            Some(diff) => QCReport::diff_rows(&diff),
+            None => vec![],
+        }
+    }
+
+    // Changes between two stored reports, e.g. a returned report and its resubmission.
+    #[method]
+    fn diff_reports(&self, old_report_id: u64, new_report_id: u64) -> Vec<(String, String, String, String, String)> {
+        match (QCReport::read_report(old_report_id), QCReport::read_report(new_report_id)) {
+            (Some((old, _)), Some((new, _))) => QCReport::diff_rows(&old.diff(&new)),
+            _ => vec![],
+        }
+    }
+
+    // *format* is text, json or csv.
+    #[method]
+    fn write_diff(&self, report_id: u64, file_path: String, format: String) -> bool {
+        let diff = match self.diff_against(report_id) {
+            Some(diff) => diff,
+            None => return false,
+        };
 
-                self.plot_data = Some(plot_data);
+        let result = match format.as_str() {
+            "text" => std::fs::write(&file_path, diff.to_text()).map_err(|e| e.into()),
+            "json" => diff.to_json().and_then(|json| std::fs::write(&file_path, json).map_err(|e| e.into())),
+            "csv"  => diff.write_csv(file_path.clone()),
+            _ => {
+                godot_error!("Unknown diff format {}", format);
+                return false;
+            }
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("Failed to write {}: {}", file_path, e);
+                false
+            }
+        }
+    }
+
+    fn diff_against(&self, report_id: u64) -> Option<QuestionnaireDiff> {
+        let q = match &self.questionnaire_data {
+            Some(q) => q,
+            None => {
+                godot_error!("Empty Questionnaire");
+                return None;
+            }
+        };
+
+        QCReport::read_report(report_id).map(|(stored, _)| stored.diff(q))
+    }
+
+    fn diff_rows(diff: &QuestionnaireDiff) -> Vec<(String, String, String, String, String)> {
+        diff.changes().iter().map(|change| {
+            (change.section.clone(), change.specification.clone(), change.kind.to_string(), change.old_value.clone(), change.new_value.clone())
+        }).collect()
+    }
+
+    // Submits the report for review.
+    #[method]
+    fn submit_report(&mut self) -> bool {
+        self.review(|q| q.submit())
+    }
+
+    #[method]
+    fn approve_report(&mut self, reviewer: String) -> bool {
+        self.review(|q| q.approve(reviewer))
+    }
+
+    // Sends the report back to the engineer, *comments* say what has to be corrected.
+    #[method]
+    fn return_report(&mut self, reviewer: String, comments: String) -> bool {
+        self.review(|q| q.return_report(reviewer, comments))
+    }
+
+    // (state, reviewer, comments), the state is one of draft, submitted, approved or returned.
+    #[method]
+    fn review_status(&self) -> (String, String, String) {
+        match &self.questionnaire_data {
+            Some(q) => {
+                let review = q.review();
+                (review.get_state().to_string(), review.get_reviewer().cloned().unwrap_or_default(), review.get_comments().clone())
             },
+            None => (String::new(), String::new(), String::new()),
+        }
+    }
+
+    #[method]
+    fn is_locked(&self) -> bool {
+        match &self.questionnaire_data {
+            Some(q) => q.review().is_locked(),
+            None => false,
+        }
+    }
+
+    // Applies a review transition, and stores it if the report is in the database.
+    fn review<F>(&mut self, transition: F) -> bool
+        where F: FnOnce(&mut Questionnaire) -> Result<(), QuestionnaireError>
+    {
+        let q = match &mut self.questionnaire_data {
+            Some(q) => q,
             None => {
-                godot_error!("No questionnaire data available");
+                godot_error!("Empty Questionnaire");
+                return false;
+            }
+        };
+
+        if let Err(e) = transition(q) {
+            godot_error!("{}", e);
+            return false;
+        }
+
+        if let Some(report_id) = self.report_id {
+            let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+            if let Err(e) = db_handle.set_report_review(report_id, &QCReport::to_db_review(q.review())) {
+                godot_error!("Failed to store review of report {}: {}", report_id, e);
+                return false;
             }
         }
+
+        true
+    }
+
+    fn to_db_review(review: &Review) -> db::ReportReview {
+        db::ReportReview::new(review.get_state().to_string(), review.get_reviewer().cloned(), review.get_comments().clone())
     }
 
     #[method]
-    fn draw_plot(&self) {
-        if let None = self.plot_data {
-            godot_error!("No Plot data available");
-            return;
+    fn build_plot(&mut self) {
+        match &self.questionnaire_data {
+            Some(qs) => {
+                self.plot_data = Some(QCReport::plot_data(qs));
+            },
+            None => {
+                godot_error!("No questionnaire data available");
+            }
         }
+    }
 
+    // Writes the chart as an SVG file, the PDF report embeds its own copy.
+    #[method]
+    fn draw_plot(&self, file_path: String) {
         match &self.plot_data {
-            Some(plot_data) => plot_data.make_plot(),
-            None => { godot_error!("No Plot data") }
+            Some(plot_data) => {
+                if let Err(e) = plot_data.make_plot(file_path) {
+                    godot_error!("Failed to draw plot: {}", e);
+                }
+            },
+            None => { godot_error!("No Plot data available") }
+        }
+    }
+
+    // Weighted compliance of each section.
+    fn plot_data(qs: &Questionnaire) -> PlotData {
+        let mut plot_data = PlotData::new();
+
+        // Section names come from the questionnaire, the general section of orphaned specifications is not in the database.
+        for section in qs.score().sections {
+            // Only N/A answers, nothing to plot.
+            if section.score.possible == 0 {
+                continue;
+            }
+
+            plot_data.add_score(section.section_id, section.title, section.score.earned, section.score.possible);
         }
+
+        plot_data
     }
 
     // Interfaces with the rust sqlite database.
-    fn acquire_questionnaire_data(&self, query: JobQuery) -> Result<Questionnaire, &'static str> {
-        // 1. Get access to database
+    fn acquire_questionnaire_data(&self, query: JobQuery) -> Result<Questionnaire, AcquireError> {
+        let mut job = QCReport::acquire_job(query.job_id)?;
+
+        // Sections requested on top of the job template.
+        for section_name in query.additional_sections.iter() {
+            let section = QCReport::acquire_additive_section(section_name)?;
+            job.merge_section(section);
+        }
+
+        QCReport::acquire_response_scales(&mut job, query.job_id)?;
+        QCReport::acquire_measurements(&mut job)?;
+        QCReport::acquire_scoring(&mut job, query.job_id)?;
+        QCReport::acquire_rules(&mut job)?;
+
+        Ok(Questionnaire::new(job))
+    }
+
+    // Scale of the job type, and of every specification that overrides it.
+    fn acquire_response_scales(job: &mut Job, job_type_id: u64) -> Result<(), AcquireError> {
         let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
-        let result = db_handle.get_all_job_specification(query.job_id);
 
-        // Add question if found
-        match result {
-            Ok(job_specs) => {
-                // At this points we have all the job specifications.
-                //godot_print!("Job Specifications - {:?}", job_specs);
+        let mut scales: HashMap<u64, ResponseScale> = HashMap::new();
+        for db_scale in db_handle.get_all_response_scales()? {
+            let scale = QCReport::to_response_scale(db_scale)?;
+            scales.insert(scale.id, scale);
+        }
 
-                let op_jobs = QCReport::job_query_to_job_questionnaire(job_specs);
+        // Without one, the job keeps the default scale.
+        if let Some(scale_id) = db_handle.get_job_type_response_scale(job_type_id)? {
+            match scales.get(&scale_id) {
+                Some(scale) => job.set_scale(scale.clone()),
+                None => return Err(AcquireError::Database(DbError::NotFound { entity: Entity::ResponseScale, key: Key::Id(scale_id) })),
+            }
+        }
 
+        for (specification_id, scale_id) in db_handle.get_all_specification_response_scales()? {
+            job.set_question_scale(&Id::<Question>::new(specification_id), scales.get(&scale_id).cloned());
+        }
 
-                match op_jobs {
-                    Ok(mut jobs_hash) => {
-                        match jobs_hash.remove(&query.job_id) {
-                            Some(job) => { 
-                                //godot_print!("{:?}",job);
-                                Ok(Questionnaire::new(job))
-                            },
-                            None     => Err("No job found with id"),
-                        }
-                    },
-                    Err(e) => Err("Failed to convert database to run-time data")
-                }
-            },
-            Err(e) => Err("Failed to access database job specifications")
+        Ok(())
+    }
+
+    fn acquire_measurements(job: &mut Job) -> Result<(), AcquireError> {
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        for (specification_id, m) in db_handle.get_all_specification_measurements()? {
+            let measurement = Measurement::new(m.unit, m.nominal_value, m.min_value, m.max_value);
+            job.set_question_kind(&Id::<Question>::new(specification_id), QuestionKind::Measurement(measurement));
+        }
+
+        Ok(())
+    }
+
+    fn acquire_scoring(job: &mut Job, job_type_id: u64) -> Result<(), AcquireError> {
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        job.set_pass_threshold(db_handle.get_job_type_pass_threshold(job_type_id)?);
+
+        for (specification_id, weight) in db_handle.get_all_specification_weights()? {
+            job.set_question_weight(&Id::<Question>::new(specification_id), weight);
+        }
+
+        Ok(())
+    }
+
+    fn acquire_rules(job: &mut Job) -> Result<(), AcquireError> {
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        // Rules about specifications the job does not have are never applied.
+        for db_rule in db_handle.get_all_specification_rules()?.iter() {
+            job.add_rule(QCReport::to_rule(db_rule)?);
+        }
+
+        Ok(())
+    }
+
+    fn to_rule(db_rule: &db::SpecificationRule) -> Result<Rule, AcquireError> {
+        let target = match (db_rule.target_specification_id, db_rule.target_section_id) {
+            (Some(specification_id), None) => RuleTarget::Question(specification_id),
+            (None, Some(section_id)) => RuleTarget::Section(section_id),
+            _ => return Err(AcquireError::RuleTarget(db_rule.id)),
+        };
+
+        let action = db_rule.action.parse::<RuleAction>()?;
+        let status = db_rule.status.parse::<QuestionStatus>()?;
+
+        Ok(Rule::new(target, action, db_rule.depends_on_specification_id, status))
+    }
+
+    // The id is given by the database.
+    fn to_db_rule(rule: &Rule) -> db::SpecificationRule {
+        let (target_specification_id, target_section_id) = match rule.target {
+            RuleTarget::Question(specification_id) => (Some(specification_id), None),
+            RuleTarget::Section(section_id) => (None, Some(section_id)),
+        };
+
+        db::SpecificationRule {
+            id: 0,
+            target_specification_id,
+            target_section_id,
+            depends_on_specification_id: rule.depends_on,
+            status: rule.status.to_string(),
+            action: rule.action.to_string(),
+        }
+    }
+
+    fn to_response_scale(db_scale: db::ResponseScale) -> Result<ResponseScale, AcquireError> {
+        let mut options = Vec::new();
+
+        for option in db_scale.options {
+            let status = option.status.parse::<QuestionStatus>()?;
+            options.push(ResponseOption::new(option.code, option.label, status));
+        }
+
+        Ok(ResponseScale::new(db_scale.id, db_scale.scale_name, options))
+    }
+
+    // Positions start at 1, as in the database.
+    fn to_db_scale(scale: &ResponseScale) -> db::ResponseScale {
+        let options = scale.options().iter().zip(1..).map(|(option, position)| {
+            db::ResponseOption { id: 0, code: option.code.clone(), label: option.label.clone(), status: option.status.to_string(), position }
+        }).collect();
+
+        db::ResponseScale { id: scale.id, scale_name: scale.name.clone(), options }
+    }
+
+    fn acquire_additive_section(section_name: &String) -> Result<Section, AcquireError> {
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+
+        let db_section = match db_handle.get_all_additive_sections()?.into_iter().find(|s| s.section_name == *section_name) {
+            Some(s) => s,
+            None => return Err(AcquireError::Database(DbError::NotFound { entity: Entity::AdditiveSection, key: Key::Name(section_name.clone()) })),
+        };
+
+        let specs = db_handle.get_section_specifications(db_section.id)?;
+
+        let mut section = Section::new(db_section.id, db_section.section_name, String::from(""), HashMap::new());
+        for spec in specs {
+            section.add_question(Question::new(Id::<Question>::new(spec.id), spec.specification_content, String::from("")));
+        }
+
+        Ok(section)
+    }
+
+    fn acquire_job(job_type_id: u64) -> Result<Job, AcquireError> {
+        // 1. Get access to database
+        let mut db_handle = DBQualityControlHandle::new("database/qcr_database.db".to_string());
+        let job_specs = db_handle.get_all_job_specification(job_type_id)?;
+
+        // At this points we have all the job specifications.
+        let mut jobs_hash = QCReport::job_query_to_job_questionnaire(job_specs)?;
+
+        match jobs_hash.remove(&job_type_id) {
+            Some(job) => Ok(job),
+            None => Err(AcquireError::Database(DbError::NotFound { entity: Entity::JobType, key: Key::Id(job_type_id) })),
         }
     }
 
@@ -202,7 +854,9 @@ impl QCReport {
     fn update_form_notes(&mut self, form_id: u64, notes: String) {
         match &mut self.questionnaire_data {
             Some(q) => {
-                q.update_form_notes(form_id, notes);
+                if let Err(e) = q.update_form_notes(form_id, notes) {
+                    QCReport::edit_error(e);
+                }
             },
             None => {
                 godot_error!("Empty Questionnaire");
@@ -217,7 +871,112 @@ impl QCReport {
         match &mut self.questionnaire_data {
             Some(q) => {
                 if let Err(e) = q.update_form_status(form_id, status) {
+                    QCReport::edit_error(e);
+                }
+            },
+            None => {
+                godot_error!("Empty Questionnaire");
+            }
+        }
+    }
+
+    // *code* is one of the options of the scale of the form.
+    #[method]
+    fn update_form_response(&mut self, form_id: u64, code: String) {
+        match &mut self.questionnaire_data {
+            Some(q) => {
+                if let Err(e) = q.update_form_response(form_id, &code) {
+                    QCReport::edit_error(e);
+                }
+            },
+            None => {
+                godot_error!("Empty Questionnaire");
+            }
+        }
+    }
+
+    // Copies the file into the attachment directory and links it to the form.
+    #[method]
+    fn add_attachment(&mut self, form_id: u64, file_path: String) -> bool {
+        let q = match &mut self.questionnaire_data {
+            Some(q) => q,
+            None => {
+                godot_error!("Empty Questionnaire");
+                return false;
+            }
+        };
+
+        let store = AttachmentStore::new(ATTACHMENT_DIRECTORY);
+        let attachment = match store.store(&file_path) {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                godot_error!("Failed to attach {}: {}", file_path, e);
+                return false;
+            }
+        };
+
+        match q.add_form_attachment(form_id, attachment) {
+            Ok(()) => true,
+            Err(e) => {
+                QCReport::edit_error(e);
+                false
+            }
+        }
+    }
+
+    // Editing an approved report is expected, e.g. from a view opened before the approval.
+    fn edit_error(e: QuestionnaireError) {
+        match e {
+            QuestionnaireError::ReportLocked => godot_warn!("{}", e),
+            _ => godot_error!("{}", e),
+        }
+    }
+
+    // The file stays in the attachment directory, other reports may use it.
+    #[method]
+    fn remove_attachment(&mut self, form_id: u64, hash: String) {
+        match &mut self.questionnaire_data {
+            Some(q) => {
+                if let Err(e) = q.remove_form_attachment(form_id, &hash) {
+                    QCReport::edit_error(e);
+                }
+            },
+            None => {
+                godot_error!("Empty Questionnaire");
+            }
+        }
+    }
+
+    // (hash, file name, media type) of the files attached to the form.
+    #[method]
+    fn list_attachments(&self, form_id: u64) -> Vec<(String, String, String)> {
+        match &self.questionnaire_data {
+            Some(q) => match q.form_attachments(form_id) {
+                Ok(attachments) => attachments.iter().map(|a| (a.hash.clone(), a.file_name.clone(), a.media_type.clone())).collect(),
+                Err(e) => {
                     godot_error!("{}", e);
+                    vec![]
+                }
+            },
+            None => vec![],
+        }
+    }
+
+    // Location of an attached file, to preview it.
+    #[method]
+    fn attachment_path(&self, hash: String) -> String {
+        let store = AttachmentStore::new(ATTACHMENT_DIRECTORY);
+        let attachment = Attachment::new(hash, String::new(), String::new());
+        store.path(&attachment).to_string_lossy().to_string()
+    }
+
+    // The status of the form is set from the limits of the measurement.
+    #[method]
+    fn update_form_value(&mut self, form_id: u64, value: f64) {
+        match &mut self.questionnaire_data {
+            Some(q) => {
+                if let Err(e) = q.update_form_value(form_id, value) {
+                    QCReport::edit_error(e);
                 }
             },
             None => {
@@ -227,18 +986,251 @@ impl QCReport {
     }
 
     #[method]
-    fn generate_report(&self, file_path: String, font_style: (String, String)) {
+    fn form_value(&self, form_id: u64) -> Option<f64> {
         match &self.questionnaire_data {
-            Some(qs) => { 
-                qs.to_pdf(file_path, String::from("Testing Document"),font_style);
-                self.draw_plot();
+            Some(q) => q.get_form(form_id).ok().and_then(|form| form.get_value()),
+            None => None,
+        }
+    }
+
+    // (unit, nominal, min, max), null if the form is not a measurement.
+    #[method]
+    fn form_measurement(&self, form_id: u64) -> Option<(String, f64, Option<f64>, Option<f64>)> {
+        let q = self.questionnaire_data.as_ref()?;
+        let form = q.get_form(form_id).ok()?;
+
+        match q.get_question(form.get_section_id(), form.get_question_id()) {
+            Ok(question) => question.get_measurement().map(|m| (m.unit.clone(), m.nominal, m.min, m.max)),
+            Err(_) => None,
+        }
+    }
+
+    // (code, label) of the options the form can be answered with, in order.
+    #[method]
+    fn form_response_options(&self, form_id: u64) -> Vec<(String, String)> {
+        match &self.questionnaire_data {
+            Some(q) => match q.form_scale(form_id) {
+                Ok(scale) => scale.options().iter().map(|option| (option.code.clone(), option.label.clone())).collect(),
+                Err(e) => {
+                    godot_error!("{}", e);
+                    vec![]
+                }
             },
-            None => { 
-                godot_error!("No questionnaire available");
+            None => vec![],
+        }
+    }
+
+    // 0 OK, 1 NO, 2 N/A or 3 unanswered. Nil for unknown forms.
+    #[method]
+    fn form_status(&self, form_id: u64) -> Option<GDQuestionStatus> {
+        match &self.questionnaire_data {
+            Some(q) => q.get_form(form_id).ok().map(|form| GDQuestionStatus(form.get_status())),
+            None => None,
+        }
+    }
+
+    // (code, label) of the chosen option, empty if the form is unanswered.
+    #[method]
+    fn form_response(&self, form_id: u64) -> (String, String) {
+        match &self.questionnaire_data {
+            Some(q) => match q.get_form(form_id).ok() {
+                Some(form) => match form.get_response() {
+                    Some(response) => (response.code.clone(), response.label.clone()),
+                    None => (String::new(), form.get_label()),
+                },
+                None => (String::new(), String::new()),
+            },
+            None => (String::new(), String::new()),
+        }
+    }
+
+    // With *include_history*, the change history of the forms is appended to the report.
+    // *font_style* is (font directory, family name), null uses the bundled font.
+    #[method]
+    fn generate_report(&mut self, file_path: String, title: String, font_style: Option<(String, String)>, include_history: bool) -> bool {
+        let options = ExportOptions { font_style, include_history, ..ExportOptions::default() };
+        let result = match &self.questionnaire_data {
+            // Every item has to be looked at, even if only to mark it N/A.
+            Some(qs) if !qs.is_complete() => {
+                Err(format!("Unable to generate report: {} unanswered specifications", qs.completeness().unanswered))
+            },
+            _ => self.try_export("pdf", file_path, title, options),
+        };
+
+        match result {
+            Ok(()) => {
+                self.last_error.clear();
+                true
+            },
+            Err(e) => {
+                godot_error!("{}", e);
+                self.last_error = e;
+                false
+            }
+        }
+    }
+
+    // Why *generate_report* last failed, empty if it succeeded.
+    #[method]
+    fn get_last_error(&self) -> String {
+        self.last_error.clone()
+    }
+
+    // Self-contained HTML version of the report, for email or publishing.
+    // Unlike the PDF, a report in progress can be exported.
+    #[method]
+    fn generate_html(&self, file_path: String, title: String) -> bool {
+        self.export("html", file_path, title, ExportOptions::default())
+    }
+
+    // Names of the formats *export_report* accepts, e.g. "pdf".
+    #[method]
+    fn export_formats(&self) -> Vec<String> {
+        self.exporters.formats().iter().map(|format| format.to_string()).collect()
+    }
+
+    // File extension of the format, without the dot. Empty for unknown formats.
+    #[method]
+    fn export_extension(&self, format: String) -> String {
+        match self.exporters.get(&format) {
+            Some(exporter) => exporter.extension().to_string(),
+            None => String::new(),
+        }
+    }
+
+    // Exports the report as any registered format.
+    #[method]
+    fn export_report(&self, format: String, file_path: String, title: String, include_history: bool) -> bool {
+        let options = ExportOptions { include_history, ..ExportOptions::default() };
+        self.export(&format, file_path, title, options)
+    }
+
+    fn export(&self, format: &str, file_path: String, title: String, options: ExportOptions) -> bool {
+        match self.try_export(format, file_path, title, options) {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("{}", e);
+                false
             }
         }
     }
 
+    fn try_export(&self, format: &str, file_path: String, title: String, mut options: ExportOptions) -> Result<(), String> {
+        match (&self.questionnaire_data, &self.header_info) {
+            (Some(qs), Some(header)) => {
+                let metadata = ReportMetadata::new(title, header.to_report_header());
+                let store = AttachmentStore::new(ATTACHMENT_DIRECTORY);
+                let plot_data = QCReport::plot_data(qs);
+
+                let source = ExportSource { questionnaire: qs, metadata: &metadata, attachments: Some(&store), chart: Some(&plot_data) };
+                options.chart_size = CHART_SIZE;
+
+                self.exporters.export_to_file(format, &source, &options, file_path.clone())
+                    .map_err(|e| format!("Failed to export {} as {}: {}", file_path, format, e))
+            },
+            (Some(_), None) => Err("No report header available".to_string()),
+            (None, _) => Err("No questionnaire available".to_string()),
+        }
+    }
+
+    // (time, engineer, field, old value, new value) of every edit of the form, oldest first.
+    #[method]
+    fn form_history(&self, form_id: u64) -> Vec<(String, String, String, String, String)> {
+        match &self.questionnaire_data {
+            Some(q) => q.form_history(form_id).iter().map(|change| {
+                (change.time_text(), change.engineer.clone(), change.field.to_string(), change.old_value.clone(), change.new_value.clone())
+            }).collect(),
+            None => vec![],
+        }
+    }
+
+    // Weighted compliance, in percent. Null when nothing was scored, e.g. only N/A answers.
+    #[method]
+    fn score(&self) -> Option<f64> {
+        match &self.questionnaire_data {
+            Some(qs) => qs.score().overall.percentage(),
+            None => None,
+        }
+    }
+
+    // (section name, weighted compliance in percent or null)
+    #[method]
+    fn section_scores(&self) -> Vec<(String, Option<f64>)> {
+        match &self.questionnaire_data {
+            Some(qs) => qs.score().sections.into_iter().map(|section| (section.title, section.score.percentage())).collect(),
+            None => vec![],
+        }
+    }
+
+    // PASS or FAIL, empty when the job type has no pass threshold or nothing was scored.
+    #[method]
+    fn verdict(&self) -> String {
+        match &self.questionnaire_data {
+            Some(qs) => qs.verdict().map(|verdict| verdict.to_string()).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    // Changes with the answers given, following the skip logic of the job.
+    #[method]
+    fn is_form_visible(&self, form_id: u64) -> bool {
+        match &self.questionnaire_data {
+            Some(qs) => qs.is_form_visible(form_id),
+            None => false,
+        }
+    }
+
+    #[method]
+    fn visible_form_ids(&self) -> Vec<u64> {
+        match &self.questionnaire_data {
+            Some(qs) => qs.visible_forms().into_iter().map(|(id, _)| *id).collect(),
+            None => vec![],
+        }
+    }
+
+    #[method]
+    fn is_complete(&self) -> bool {
+        match &self.questionnaire_data {
+            Some(qs) => qs.is_complete(),
+            None => false,
+        }
+    }
+
+    // (total, answered, unanswered)
+    #[method]
+    fn completeness(&self) -> (usize, usize, usize) {
+        match &self.questionnaire_data {
+            Some(qs) => {
+                let completeness = qs.completeness();
+                (completeness.total, completeness.answered, completeness.unanswered)
+            },
+            None => (0, 0, 0),
+        }
+    }
+
+    // Form ids still to be answered, grouped by section name.
+    #[method]
+    fn unanswered_forms(&self) -> Vec<(String, Vec<u64>)> {
+        match &self.questionnaire_data {
+            Some(qs) => {
+                let mut unanswered = Vec::new();
+
+                for (section_id, form_ids) in qs.unanswered_forms() {
+                    let section_name = match qs.get_section(&Id::<Section>::new(section_id)) {
+                        Ok(section) => section.get_title(),
+                        Err(_) => String::from(""),
+                    };
+
+                    unanswered.push((section_name, form_ids));
+                }
+
+                unanswered.sort_by(|a, b| a.0.cmp(&b.0));
+                unanswered
+            },
+            None => vec![],
+        }
+    }
+
     #[method]
     fn all_form_fields(&self) -> Vec<(u64, String, String)> {
         let result = &self.questionnaire_data;
@@ -273,7 +1265,7 @@ impl QCReport {
         Questionnaire::new(job)
     }
 
-    fn job_query_to_job_questionnaire(job_queries: Vec<JobSpecificationSection>) -> Result<HashMap<u64, Job>, &'static str> {
+    fn job_query_to_job_questionnaire(job_queries: Vec<JobSpecificationSection>) -> Result<HashMap<u64, Job>, QuestionnaireError> {
         let mut hash_jobs = HashMap::new();
 
         for job_spec in job_queries.iter() {
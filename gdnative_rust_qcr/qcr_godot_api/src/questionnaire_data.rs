@@ -14,7 +14,8 @@ impl ToVariant for GDQuestionStatus {
         match self.0 {
             QuestionStatus::OK => 0.to_variant(),
             QuestionStatus::NO => 1.to_variant(),
-            QuestionStatus::NA => 2.to_variant()
+            QuestionStatus::NA => 2.to_variant(),
+            QuestionStatus::Unanswered => 3.to_variant()
         }
     }
 }
@@ -27,9 +28,10 @@ impl FromVariant for GDQuestionStatus {
             0 => Ok(GDQuestionStatus(QuestionStatus::OK)),
             1 => Ok(GDQuestionStatus(QuestionStatus::NO)),
             2 => Ok(GDQuestionStatus(QuestionStatus::NA)),
+            3 => Ok(GDQuestionStatus(QuestionStatus::Unanswered)),
             _ => Err(FromVariantError::UnknownEnumVariant {
                 variant: "i64".to_owned(),
-                expected: &["0","1","2"],
+                expected: &["0","1","2","3"],
             }),
         }
     }
@@ -0,0 +1,126 @@
+/*
+ * Evidence attached to unit forms: photos, log excerpts, PDFs.
+ *
+ * Files are kept in a content-addressed directory, named after the SHA-256 of their content,
+ * so the same file attached twice is only stored once. Forms only hold a reference.
+ */
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    // SHA-256 of the content, in hexadecimal.
+    pub hash: String,
+    // Name of the file when it was attached.
+    pub file_name: String,
+    pub media_type: String,
+}
+
+impl Attachment {
+    pub fn new(hash: String, file_name: String, media_type: String) -> Self {
+        Attachment { hash, file_name, media_type }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.media_type.starts_with("image/")
+    }
+}
+
+// Guessed from the extension, attachments are not inspected.
+pub fn media_type(file_name: &str) -> String {
+    let extension = Path::new(file_name).extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let media_type = match extension.as_str() {
+        "png"          => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "bmp"          => "image/bmp",
+        "pdf"          => "application/pdf",
+        "txt" | "log"  => "text/plain",
+        "csv"          => "text/csv",
+        _              => "application/octet-stream",
+    };
+
+    media_type.to_string()
+}
+
+pub struct AttachmentStore {
+    root: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        AttachmentStore { root: root.into() }
+    }
+
+    // Copies the file into the store.
+    pub fn store(&self, source: impl AsRef<Path>) -> Result<Attachment, Box<dyn Error>> {
+        let source = source.as_ref();
+        let content = fs::read(source)?;
+
+        let file_name = match source.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_string(),
+            None => return Err("Attachment has no file name".into()),
+        };
+
+        let hash = format!("{:x}", Sha256::digest(&content));
+
+        fs::create_dir_all(&self.root)?;
+        let destination = self.root.join(&hash);
+        if !destination.exists() {
+            fs::write(&destination, &content)?;
+        }
+
+        let media_type = media_type(&file_name);
+        Ok(Attachment::new(hash, file_name, media_type))
+    }
+
+    pub fn path(&self, attachment: &Attachment) -> PathBuf {
+        self.root.join(&attachment.hash)
+    }
+
+    pub fn contains(&self, attachment: &Attachment) -> bool {
+        self.path(attachment).is_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_types() {
+        assert_eq!("image/jpeg", media_type("IMG_0001.JPG"));
+        assert_eq!("application/pdf", media_type("pressure_test.pdf"));
+        assert_eq!("application/octet-stream", media_type("no_extension"));
+    }
+
+    #[test]
+    fn store_is_content_addressed() {
+        let dir = std::env::temp_dir().join("qcr_attachment_store_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let first = dir.join("first.log");
+        let second = dir.join("second.log");
+        fs::write(&first, "pressure held at 5000 psi").unwrap();
+        fs::write(&second, "pressure held at 5000 psi").unwrap();
+
+        let store = AttachmentStore::new(dir.join("attachments"));
+        let a = store.store(&first).unwrap();
+        let b = store.store(&second).unwrap();
+
+        assert_eq!(a.hash, b.hash);
+        assert_eq!("second.log", b.file_name);
+        assert!(store.contains(&a));
+        assert_eq!(1, fs::read_dir(dir.join("attachments")).unwrap().count());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,10 +1,19 @@
 use std::marker::PhantomData;
 use std::collections::HashMap;
 
+use crate::scale::ResponseScale;
+use crate::questionnaire::QuestionStatus;
+use crate::error::QuestionnaireError;
+
+use serde::{Serialize, Deserialize};
+
 // ID
-#[derive(PartialEq, Eq, Debug, Clone, Copy,Hash)]
+// Stored as the bare primitive, the marker only exists at compile time.
+#[derive(PartialEq, Eq, Debug, Clone, Copy,Hash, Serialize, Deserialize)]
+#[serde(transparent, bound = "")]
 pub struct Id<T> {
     pub id: u64,
+    #[serde(skip)]
     marker: PhantomData<T>,
 }
 
@@ -21,11 +30,64 @@ impl<T> Id<T> {
 
 // -----------
 
-#[derive(Debug, Clone )]
+// A value to be measured, accepted when it lies within the limits.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Measurement {
+    pub unit: String,
+    pub nominal: f64,
+    // A missing limit is not checked.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Measurement {
+    pub fn new(unit: String, nominal: f64, min: Option<f64>, max: Option<f64>) -> Self {
+        Measurement { unit, nominal, min, max }
+    }
+
+    // Nominal value plus or minus *tolerance*.
+    pub fn with_tolerance(unit: String, nominal: f64, tolerance: f64) -> Self {
+        Measurement::new(unit, nominal, Some(nominal - tolerance), Some(nominal + tolerance))
+    }
+
+    pub fn evaluate(&self, value: f64) -> QuestionStatus {
+        let above_min = self.min.is_none_or(|min| value >= min);
+        let below_max = self.max.is_none_or(|max| value <= max);
+
+        if above_min && below_max {
+            QuestionStatus::OK
+        } else {
+            QuestionStatus::NO
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum QuestionKind {
+    // Answered with an option of the response scale.
+    #[default]
+    Checklist,
+    // Answered with a value, its status follows from the limits.
+    Measurement(Measurement),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Question {
     id: Id<Question>,
     text: String,
     description: String,
+    // Overrides the scale of the job when set.
+    #[serde(default)]
+    scale: Option<ResponseScale>,
+    #[serde(default)]
+    kind: QuestionKind,
+    // How much the question counts towards the score, 0 is not scored.
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
 }
 
 impl Question {
@@ -34,9 +96,43 @@ impl Question {
             id,
             text,
             description,
+            scale: None,
+            kind: QuestionKind::Checklist,
+            weight: default_weight(),
+        }
+    }
+
+    pub fn set_weight(&mut self, weight: u32) {
+        self.weight = weight;
+    }
+
+    pub fn get_weight(&self) -> u32 {
+        self.weight
+    }
+
+    pub fn set_kind(&mut self, kind: QuestionKind) {
+        self.kind = kind;
+    }
+
+    pub fn get_kind(&self) -> &QuestionKind {
+        &self.kind
+    }
+
+    pub fn get_measurement(&self) -> Option<&Measurement> {
+        match &self.kind {
+            QuestionKind::Measurement(measurement) => Some(measurement),
+            QuestionKind::Checklist => None
         }
     }
 
+    pub fn set_scale(&mut self, scale: Option<ResponseScale>) {
+        self.scale = scale;
+    }
+
+    pub fn get_scale(&self) -> Option<&ResponseScale> {
+        self.scale.as_ref()
+    }
+
     pub fn get_id(&self) -> Id<Question> {
         self.id.clone()
     }
@@ -52,7 +148,7 @@ impl PartialEq for Question {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Section {
     pub id: Id<Section>,
     title: String,
@@ -100,24 +196,37 @@ impl Section {
     }
 
     pub fn add_question(&mut self, question: Question) {
-        if self.has_question(&question.get_id()) == false {
+        if !self.has_question(&question.get_id()) {
             self.questions.insert(question.get_id().primitive(), question);
         }
     }
 
-    pub fn remove_question(&mut self, id: &Id<Question>) -> Result<(), &'static str>{
+    pub fn remove_question(&mut self, id: &Id<Question>) -> Result<(), QuestionnaireError>{
         match self.questions.remove(&id.primitive()) {
             Some(_) => Ok(()),
-            None   => Err("No question to remove")
+            None   => Err(self.missing_question(id))
         }
     }
 
-    pub fn get_question(&self, id: &Id<Question>) -> Result<&Question, &'static str> {
+    pub fn get_question(&self, id: &Id<Question>) -> Result<&Question, QuestionnaireError> {
         match self.questions.get(&id.primitive()) {
             Some(q) => Ok(q),
-            None    => Err("No question found")
+            None    => Err(self.missing_question(id))
+        }
+
+    }
+
+    pub fn get_mut_question(&mut self, id: &Id<Question>) -> Result<&mut Question, QuestionnaireError> {
+        let error = self.missing_question(id);
+
+        match self.questions.get_mut(&id.primitive()) {
+            Some(q) => Ok(q),
+            None    => Err(error)
         }
+    }
 
+    fn missing_question(&self, id: &Id<Question>) -> QuestionnaireError {
+        QuestionnaireError::MissingQuestion { section_id: self.id.primitive(), question_id: id.primitive() }
     }
 
     pub fn all_questions(&self) -> &HashMap<u64,Question> {
@@ -133,10 +242,11 @@ impl Section {
 
 #[cfg(test)]
 mod tests{
-    use super::{Question,Id, Section};
+    use super::{Question,Id, Section, QuestionKind, Measurement};
+    use crate::questionnaire::QuestionStatus;
 
     mod test_question {
-        use super::{Question, Id, Section};
+        use super::{Question, Id, Section, QuestionKind};
 
         #[test]
         fn basic_question() {
@@ -145,12 +255,46 @@ mod tests{
                 id: Id::<Question>::new(0),
                 text: String::from("Will this work?"),
                 description: String::from("Testing Question"),
+                scale: None,
+                kind: QuestionKind::Checklist,
+                weight: 1,
             };
 
             assert_eq!(q_expect, q1);
         }
     }
 
+    mod test_measurement {
+        use super::*;
+
+        #[test]
+        fn evaluate_tolerance() {
+            let density = Measurement::with_tolerance(String::from("ppg"), 15.8, 0.2);
+
+            assert_eq!(QuestionStatus::OK, density.evaluate(15.8));
+            assert_eq!(QuestionStatus::OK, density.evaluate(15.65));
+            assert_eq!(QuestionStatus::NO, density.evaluate(16.1));
+            assert_eq!(QuestionStatus::NO, density.evaluate(15.5));
+        }
+
+        #[test]
+        fn evaluate_open_limit() {
+            let pressure = Measurement::new(String::from("psi"), 5000.0, Some(4800.0), None);
+
+            assert_eq!(QuestionStatus::OK, pressure.evaluate(7000.0));
+            assert_eq!(QuestionStatus::NO, pressure.evaluate(4700.0));
+        }
+
+        #[test]
+        fn question_measurement() {
+            let mut question = Question::new(Id::<Question>::new(0), String::from("Slurry density"), String::from(""));
+            assert_eq!(None, question.get_measurement());
+
+            question.set_kind(QuestionKind::Measurement(Measurement::with_tolerance(String::from("ppg"), 15.8, 0.2)));
+            assert_eq!("ppg", question.get_measurement().unwrap().unit);
+        }
+    }
+
     mod test_section {
         use super::*;
 
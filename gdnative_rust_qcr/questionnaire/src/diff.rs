@@ -0,0 +1,233 @@
+/*
+ * Differences between two revisions of a questionnaire, e.g. a returned report and its resubmission.
+ *
+ * Forms are matched by section and specification, not by form id, so that
+ * questionnaires built or loaded separately can be compared.
+ */
+use crate::questionnaire::{Questionnaire, UnitForm};
+use crate::CSVWrite;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Status,
+    Notes,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Status => write!(f, "status"),
+            ChangeKind::Notes => write!(f, "notes"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct SpecificationChange {
+    pub section_id: u64,
+    pub section: String,
+    pub specification_id: u64,
+    pub specification: String,
+    pub kind: ChangeKind,
+    // Answer of an added or removed specification, otherwise the value that changed.
+    pub old_value: String,
+    pub new_value: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct QuestionnaireDiff {
+    // Ordered by section, then specification.
+    changes: Vec<SpecificationChange>,
+}
+
+impl QuestionnaireDiff {
+    pub fn changes(&self) -> &Vec<SpecificationChange> {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    // (section title, changes of the section), in section order.
+    pub fn by_section(&self) -> Vec<(String, Vec<&SpecificationChange>)> {
+        let mut sections: Vec<(u64, String, Vec<&SpecificationChange>)> = Vec::new();
+
+        for change in self.changes.iter() {
+            match sections.last_mut() {
+                Some((section_id, _, changes)) if *section_id == change.section_id => changes.push(change),
+                _ => sections.push((change.section_id, change.section.clone(), vec![change])),
+            }
+        }
+
+        sections.into_iter().map(|(_, title, changes)| (title, changes)).collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return String::from("No changes\n");
+        }
+
+        let mut s = String::new();
+
+        for (section, changes) in self.by_section() {
+            s.push_str(&format!("{}\n", section));
+
+            for change in changes {
+                let line = match change.kind {
+                    ChangeKind::Added => format!("  + {}\n", change.specification),
+                    ChangeKind::Removed => format!("  - {}\n", change.specification),
+                    ChangeKind::Status | ChangeKind::Notes => {
+                        format!("  ~ {}: {} \"{}\" -> \"{}\"\n", change.specification, change.kind, change.old_value, change.new_value)
+                    },
+                };
+                s.push_str(&line);
+            }
+        }
+
+        s
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(&self.changes)?)
+    }
+}
+
+impl CSVWrite for QuestionnaireDiff {
+    fn write_csv(&self, file_path: String) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_path(file_path)?;
+
+        for change in self.changes.iter() {
+            wtr.serialize(change)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+// Changes that turn *old* into *new*.
+pub fn diff(old: &Questionnaire, new: &Questionnaire) -> QuestionnaireDiff {
+    let old_forms = keyed_forms(old);
+    let new_forms = keyed_forms(new);
+
+    let mut changes = Vec::new();
+
+    for (key, old_form) in old_forms.iter() {
+        match new_forms.get(key) {
+            Some(new_form) => {
+                if old_form.get_label() != new_form.get_label() {
+                    changes.push(change(new, new_form, ChangeKind::Status, old_form.get_label(), new_form.get_label()));
+                }
+
+                if old_form.get_notes() != new_form.get_notes() {
+                    changes.push(change(new, new_form, ChangeKind::Notes, old_form.get_notes(), new_form.get_notes()));
+                }
+            },
+            None => changes.push(change(old, old_form, ChangeKind::Removed, old_form.get_label(), String::new())),
+        }
+    }
+
+    for (key, new_form) in new_forms.iter() {
+        if !old_forms.contains_key(key) {
+            changes.push(change(new, new_form, ChangeKind::Added, String::new(), new_form.get_label()));
+        }
+    }
+
+    changes.sort_by_key(|c| (c.section_id, c.specification_id));
+
+    QuestionnaireDiff { changes }
+}
+
+fn keyed_forms(questionnaire: &Questionnaire) -> BTreeMap<(u64, u64), &UnitForm> {
+    questionnaire.forms.values()
+        .map(|form| ((form.get_section_id().primitive(), form.get_question_id().primitive()), form))
+        .collect()
+}
+
+fn change(questionnaire: &Questionnaire, form: &UnitForm, kind: ChangeKind, old_value: String, new_value: String) -> SpecificationChange {
+    let section = match questionnaire.get_section(form.get_section_id()) {
+        Ok(section) => section.get_title(),
+        Err(_) => String::new(),
+    };
+
+    let specification = match questionnaire.get_question(form.get_section_id(), form.get_question_id()) {
+        Ok(question) => question.get_title(),
+        Err(_) => String::new(),
+    };
+
+    SpecificationChange {
+        section_id: form.get_section_id().primitive(),
+        section,
+        specification_id: form.get_question_id().primitive(),
+        specification,
+        kind,
+        old_value,
+        new_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+    use crate::questionnaire::QuestionStatus;
+    use std::collections::HashMap;
+
+    fn questionnaire_test(question_ids: &[u64]) -> Questionnaire {
+        let mut section = Section::new(0, String::from("section 1"), String::new(), HashMap::new());
+        for id in question_ids {
+            section.add_question(Question::new(Id::<Question>::new(*id), format!("question {}", id), String::new()));
+        }
+
+        let mut job = Job::new(0, String::from("job 1"), String::new(), HashMap::new());
+        job.add_section(section);
+
+        Questionnaire::new(job)
+    }
+
+    fn form_id(qs: &Questionnaire, question_id: u64) -> u64 {
+        *qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == question_id).unwrap().0
+    }
+
+    #[test]
+    fn diff_questionnaires() {
+        let mut old = questionnaire_test(&[0, 1, 2]);
+        let mut new = questionnaire_test(&[0, 1, 3]);
+
+        old.update_form_status(form_id(&old, 0), QuestionStatus::NO).unwrap();
+        new.update_form_status(form_id(&new, 0), QuestionStatus::OK).unwrap();
+        new.update_form_notes(form_id(&new, 1), String::from("Checked twice")).unwrap();
+
+        let d = diff(&old, &new);
+        let kinds: Vec<(u64, ChangeKind)> = d.changes().iter().map(|c| (c.specification_id, c.kind)).collect();
+
+        assert_eq!(vec![(0, ChangeKind::Status), (1, ChangeKind::Notes), (2, ChangeKind::Removed), (3, ChangeKind::Added)], kinds);
+        assert_eq!("NO", d.changes()[0].old_value);
+        assert_eq!("OK", d.changes()[0].new_value);
+        assert_eq!(1, d.by_section().len());
+        assert!(d.to_text().contains("  - question 2\n"));
+        assert!(d.to_json().unwrap().contains("\"kind\": \"removed\""));
+    }
+
+    #[test]
+    fn no_changes() {
+        let qs = questionnaire_test(&[0, 1]);
+        let d = diff(&qs, &questionnaire_test(&[0, 1]));
+
+        assert!(d.is_empty());
+        assert_eq!("No changes\n", d.to_text());
+    }
+}
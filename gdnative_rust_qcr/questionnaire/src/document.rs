@@ -0,0 +1,143 @@
+/*
+ * On-disk format for a questionnaire that is still being filled in.
+ *
+ * The document stores the job snapshot, every unit form and the header of the report,
+ * so that a review can be closed and resumed later on.
+ */
+use crate::questionnaire::Questionnaire;
+
+use std::error::Error;
+use std::fs;
+
+use serde::{Serialize, Deserialize};
+
+// Increase when a change to the format can not be read by older versions.
+pub const DOCUMENT_VERSION: u32 = 1;
+
+// Information about who is filling the report, and when.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReportHeader {
+    pub engineer: String,
+    pub job: String,
+    pub day: i64,
+    pub month: i64,
+    pub year: i64,
+}
+
+impl ReportHeader {
+    pub fn new(engineer: String, job: String, day: i64, month: i64, year: i64) -> Self {
+        ReportHeader { engineer, job, day, month, year }
+    }
+}
+
+// Borrowed version of the document, to avoid cloning the questionnaire when saving.
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    header: &'a ReportHeader,
+    questionnaire: &'a Questionnaire,
+}
+
+// The version was already checked with *DocumentVersion*.
+#[derive(Deserialize)]
+struct Document {
+    header: ReportHeader,
+    questionnaire: Questionnaire,
+}
+
+// Only the version is read first, so newer documents fail with a clear message.
+#[derive(Deserialize)]
+struct DocumentVersion {
+    version: u32,
+}
+
+pub fn to_json(header: &ReportHeader, questionnaire: &Questionnaire) -> Result<String, Box<dyn Error>> {
+    let document = DocumentRef {
+        version: DOCUMENT_VERSION,
+        header,
+        questionnaire,
+    };
+
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+pub fn from_json(data: &str) -> Result<(ReportHeader, Questionnaire), Box<dyn Error>> {
+    let DocumentVersion { version } = serde_json::from_str(data)?;
+
+    if version > DOCUMENT_VERSION {
+        return Err(format!("Unsupported document version {} (latest supported is {})", version, DOCUMENT_VERSION).into());
+    }
+
+    let mut document: Document = serde_json::from_str(data)?;
+
+    // Edits made after resuming are recorded under the engineer of the report.
+    document.questionnaire.set_engineer(document.header.engineer.clone());
+
+    Ok((document.header, document.questionnaire))
+}
+
+pub fn save_questionnaire(file_path: String, header: &ReportHeader, questionnaire: &Questionnaire) -> Result<(), Box<dyn Error>> {
+    let data = to_json(header, questionnaire)?;
+    fs::write(file_path, data)?;
+
+    Ok(())
+}
+
+pub fn load_questionnaire(file_path: String) -> Result<(ReportHeader, Questionnaire), Box<dyn Error>> {
+    let data = fs::read_to_string(file_path)?;
+
+    from_json(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+    use crate::questionnaire::QuestionStatus;
+    use std::collections::HashMap;
+
+    fn questionnaire_test() -> Questionnaire {
+        let q1 = Question::new(Id::<Question>::new(0), String::from("question 1"), String::from("my question"));
+        let q2 = Question::new(Id::<Question>::new(1), String::from("question 2"), String::from("my question"));
+        let mut section = Section::new(0, String::from("section 1"), String::from("my section"), HashMap::new());
+        section.add_question(q1);
+        section.add_question(q2);
+        let mut job = Job::new(0, String::from("job 1"), String::from("my job"), HashMap::new());
+        job.add_section(section);
+
+        Questionnaire::new(job)
+    }
+
+    fn header_test() -> ReportHeader {
+        ReportHeader::new(String::from("Engineer"), String::from("Well 7"), 1, 2, 2023)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut qs = questionnaire_test();
+        qs.update_form_status(1, QuestionStatus::NO).unwrap();
+        qs.update_form_notes(1, String::from("Missing signature")).unwrap();
+
+        let data = to_json(&header_test(), &qs).unwrap();
+        let (header, loaded) = from_json(&data).unwrap();
+
+        assert_eq!(header_test(), header);
+        assert_eq!(qs.all_forms(), loaded.all_forms());
+
+        for (_, form) in loaded.all_forms() {
+            let expected = qs.get_question(form.get_section_id(), form.get_question_id()).unwrap();
+            let actual = loaded.get_question(form.get_section_id(), form.get_question_id()).unwrap();
+
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn newer_version_rejected() {
+        let data = to_json(&header_test(), &questionnaire_test()).unwrap();
+        let data = data.replace(&format!("\"version\": {}", DOCUMENT_VERSION), "\"version\": 9999");
+
+        assert!(from_json(&data).is_err());
+    }
+}
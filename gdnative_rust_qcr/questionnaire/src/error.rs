@@ -0,0 +1,74 @@
+/*
+ * Errors of the questionnaire crate.
+ *
+ * Variants carry the ids involved, so callers can tell which item failed
+ * and react to a specific failure instead of matching on messages.
+ */
+use crate::review::ReviewState;
+
+use std::error::Error;
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum QuestionnaireError {
+    MissingSection(u64),
+    MissingQuestion { section_id: u64, question_id: u64 },
+    MissingOrphanedQuestion(u64),
+    DuplicateQuestion { section_id: u64, question_id: u64 },
+    MissingForm(u64),
+    MissingAttachment { form_id: u64, hash: String },
+    // Code that is not an option of the scale of the form.
+    UnknownResponse { form_id: u64, code: String },
+    NotAMeasurement(u64),
+    InvalidMeasurement,
+    // Approved reports can not be edited.
+    ReportLocked,
+    InvalidTransition { from: ReviewState, to: ReviewState },
+    MissingReviewer,
+    MissingComments,
+    // Merge of two copies of a questionnaire.
+    DifferentJobs,
+    DifferentSpecifications,
+    NoConflict(u64),
+    UnresolvedConflicts(usize),
+    // Text that does not name a value, e.g. an unknown status.
+    Unknown { kind: &'static str, value: String },
+}
+
+impl Display for QuestionnaireError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuestionnaireError::MissingSection(id) => write!(f, "No section with id {}", id),
+            QuestionnaireError::MissingQuestion { section_id, question_id } => write!(f, "No specification with id {} in section {}", question_id, section_id),
+            QuestionnaireError::MissingOrphanedQuestion(id) => write!(f, "No orphaned specification with id {}", id),
+            QuestionnaireError::DuplicateQuestion { section_id, question_id } => write!(f, "Specification {} is already in section {}", question_id, section_id),
+            QuestionnaireError::MissingForm(id) => write!(f, "No form with id {}", id),
+            QuestionnaireError::MissingAttachment { form_id, hash } => write!(f, "No attachment {} on form {}", hash, form_id),
+            QuestionnaireError::UnknownResponse { form_id, code } => write!(f, "Unknown response \"{}\" for the scale of form {}", code, form_id),
+            QuestionnaireError::NotAMeasurement(id) => write!(f, "Form {} is not a measurement", id),
+            QuestionnaireError::InvalidMeasurement => write!(f, "Invalid measurement value"),
+            QuestionnaireError::ReportLocked => write!(f, "Approved reports can not be edited"),
+            QuestionnaireError::InvalidTransition { from, to } => write!(f, "A {} report can not be {}", from, to),
+            QuestionnaireError::MissingReviewer => write!(f, "A reviewer is required"),
+            QuestionnaireError::MissingComments => write!(f, "Returned reports need comments"),
+            QuestionnaireError::DifferentJobs => write!(f, "Questionnaires are not from the same job"),
+            QuestionnaireError::DifferentSpecifications => write!(f, "Questionnaires do not have the same specifications"),
+            QuestionnaireError::NoConflict(id) => write!(f, "No conflict on form {}", id),
+            QuestionnaireError::UnresolvedConflicts(count) => write!(f, "Merge has {} unresolved conflicts", count),
+            QuestionnaireError::Unknown { kind, value } => write!(f, "Unknown {} \"{}\"", kind, value),
+        }
+    }
+}
+
+impl Error for QuestionnaireError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_ids() {
+        assert_eq!("No specification with id 4 in section 2", QuestionnaireError::MissingQuestion { section_id: 2, question_id: 4 }.to_string());
+        assert_eq!("A draft report can not be approved", QuestionnaireError::InvalidTransition { from: ReviewState::Draft, to: ReviewState::Approved }.to_string());
+    }
+}
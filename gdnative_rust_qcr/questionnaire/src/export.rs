@@ -0,0 +1,289 @@
+/*
+ * Report exporters, looked up by format name.
+ *
+ * Each output format implements *ReportExporter* and is registered in an
+ * *ExporterRegistry*, so callers can offer every format without knowing them.
+ * In-house formats only need to be registered, see *ExporterRegistry::register*.
+ */
+use crate::questionnaire::Questionnaire;
+use crate::attachment::AttachmentStore;
+use crate::pdf::{PdfOptions, RasterImage, ReportMetadata};
+use crate::json::{QuestionnaireExport, SectionStatistics};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+// Compliance chart of the report, drawn on demand by the exporters that show it.
+// Implemented by *plotting::PlotData*, which this crate can not depend on.
+pub trait ChartSource {
+    fn raster(&self, dimensions: (u32, u32)) -> Result<RasterImage, Box<dyn Error>>;
+    fn svg(&self, dimensions: (u32, u32)) -> Result<String, Box<dyn Error>>;
+    fn statistics(&self) -> Vec<SectionStatistics>;
+}
+
+// What is exported.
+pub struct ExportSource<'a> {
+    pub questionnaire: &'a Questionnaire,
+    pub metadata: &'a ReportMetadata,
+    pub attachments: Option<&'a AttachmentStore>,
+    pub chart: Option<&'a dyn ChartSource>,
+}
+
+impl<'a> ExportSource<'a> {
+    pub fn new(questionnaire: &'a Questionnaire, metadata: &'a ReportMetadata) -> Self {
+        ExportSource { questionnaire, metadata, attachments: None, chart: None }
+    }
+
+    // A report without a chart is still exported, e.g. when every answer is N/A.
+    fn chart_raster(&self, dimensions: (u32, u32)) -> Option<RasterImage> {
+        self.chart.and_then(|chart| chart.raster(dimensions).ok())
+    }
+
+    fn chart_svg(&self, dimensions: (u32, u32)) -> Option<String> {
+        self.chart.and_then(|chart| chart.svg(dimensions).ok())
+    }
+}
+
+// How it is exported, exporters ignore the options they do not use.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExportOptions {
+    // (font directory, family name), None uses the bundled font.
+    pub font_style: Option<(String, String)>,
+    pub include_history: bool,
+    pub chart_size: (u32, u32),
+    // Settings of other exporters, by name.
+    pub extra: HashMap<String, String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions { font_style: None, include_history: false, chart_size: (1024, 640), extra: HashMap::new() }
+    }
+}
+
+pub trait ReportExporter {
+    // Name the format is looked up with, e.g. "pdf".
+    fn format(&self) -> &str;
+    // File extension, without the dot.
+    fn extension(&self) -> &str;
+    fn export(&self, source: &ExportSource, options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>>;
+}
+
+pub struct PdfExporter;
+
+impl ReportExporter for PdfExporter {
+    fn format(&self) -> &str {
+        "pdf"
+    }
+
+    fn extension(&self) -> &str {
+        "pdf"
+    }
+
+    fn export(&self, source: &ExportSource, options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let chart = source.chart_raster(options.chart_size);
+        let pdf_options = PdfOptions { attachments: source.attachments, history: options.include_history, chart: chart.as_ref() };
+
+        source.questionnaire.write_pdf(w, source.metadata, options.font_style.clone(), &pdf_options)?;
+
+        Ok(())
+    }
+}
+
+pub struct HtmlExporter;
+
+impl ReportExporter for HtmlExporter {
+    fn format(&self) -> &str {
+        "html"
+    }
+
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn export(&self, source: &ExportSource, options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let chart = source.chart_svg(options.chart_size);
+
+        w.write_all(source.questionnaire.to_html(source.metadata, chart.as_deref()).as_bytes())?;
+
+        Ok(())
+    }
+}
+
+pub struct JsonExporter;
+
+impl ReportExporter for JsonExporter {
+    fn format(&self) -> &str {
+        "json"
+    }
+
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn export(&self, source: &ExportSource, _options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let statistics = source.chart.map(|chart| chart.statistics()).unwrap_or_default();
+        let export = QuestionnaireExport::new(source.questionnaire, &source.metadata.header, statistics);
+
+        w.write_all(export.to_json()?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+pub struct CsvExporter;
+
+impl ReportExporter for CsvExporter {
+    fn format(&self) -> &str {
+        "csv"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn export(&self, source: &ExportSource, _options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        source.questionnaire.write_csv_to(w)
+    }
+}
+
+pub struct ExporterRegistry {
+    // In registration order.
+    exporters: Vec<Box<dyn ReportExporter>>,
+}
+
+// Registry of the built-in formats.
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        let mut registry = ExporterRegistry::new();
+        registry.register(Box::new(PdfExporter));
+        registry.register(Box::new(HtmlExporter));
+        registry.register(Box::new(JsonExporter));
+        registry.register(Box::new(CsvExporter));
+        registry
+    }
+}
+
+impl ExporterRegistry {
+    // Registry without any format.
+    pub fn new() -> Self {
+        ExporterRegistry { exporters: Vec::new() }
+    }
+
+    // Replaces the exporter already registered for the same format.
+    pub fn register(&mut self, exporter: Box<dyn ReportExporter>) {
+        match self.exporters.iter().position(|e| e.format() == exporter.format()) {
+            Some(index) => self.exporters[index] = exporter,
+            None => self.exporters.push(exporter),
+        }
+    }
+
+    pub fn get(&self, format: &str) -> Option<&dyn ReportExporter> {
+        self.exporters.iter().find(|e| e.format() == format).map(|e| e.as_ref())
+    }
+
+    pub fn formats(&self) -> Vec<&str> {
+        self.exporters.iter().map(|e| e.format()).collect()
+    }
+
+    pub fn export(&self, format: &str, source: &ExportSource, options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        match self.get(format) {
+            Some(exporter) => exporter.export(source, options, w),
+            None => Err(format!("Unknown export format {}", format).into()),
+        }
+    }
+
+    // The file is only created once the format is known.
+    pub fn export_to_file(&self, format: &str, source: &ExportSource, options: &ExportOptions, file_path: String) -> Result<(), Box<dyn Error>> {
+        let exporter = match self.get(format) {
+            Some(exporter) => exporter,
+            None => return Err(format!("Unknown export format {}", format).into()),
+        };
+
+        let mut file = File::create(file_path)?;
+        exporter.export(source, options, &mut file)?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::document::ReportHeader;
+    use crate::job::Job;
+    use crate::questionnaire::QuestionStatus;
+
+    fn questionnaire_test() -> Questionnaire {
+        let mut section = Section::new(0, String::from("Cover"), String::new(), HashMap::new());
+        section.add_question(Question::new(Id::<Question>::new(0), String::from("Title"), String::new()));
+        let mut job = Job::new(0, String::from("Cementing"), String::new(), HashMap::new());
+        job.add_section(section);
+
+        let mut qs = Questionnaire::new(job);
+        qs.update_form_status(0, QuestionStatus::OK).unwrap();
+        qs
+    }
+
+    fn metadata_test() -> ReportMetadata {
+        ReportMetadata::new(String::from("QC Report"), ReportHeader::new(String::from("J. Doe"), String::from("Well 12"), 3, 7, 2023))
+    }
+
+    struct MarkdownExporter;
+
+    impl ReportExporter for MarkdownExporter {
+        fn format(&self) -> &str {
+            "markdown"
+        }
+
+        fn extension(&self) -> &str {
+            "md"
+        }
+
+        fn export(&self, source: &ExportSource, _options: &ExportOptions, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+            writeln!(w, "# {}", source.metadata.title)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn builtin_formats() {
+        let registry = ExporterRegistry::default();
+        assert_eq!(vec!["pdf", "html", "json", "csv"], registry.formats());
+
+        let qs = questionnaire_test();
+        let metadata = metadata_test();
+        let source = ExportSource::new(&qs, &metadata);
+
+        let mut csv = Vec::new();
+        registry.export("csv", &source, &ExportOptions::default(), &mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().contains("0,0,Cover,Title,,OK"));
+
+        let mut json = Vec::new();
+        registry.export("json", &source, &ExportOptions::default(), &mut json).unwrap();
+        assert!(String::from_utf8(json).unwrap().contains("\"schema_version\": 1"));
+
+        assert!(registry.export("docx", &source, &ExportOptions::default(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn register_format() {
+        let mut registry = ExporterRegistry::default();
+        registry.register(Box::new(MarkdownExporter));
+        registry.register(Box::new(MarkdownExporter));
+
+        assert_eq!(5, registry.formats().len());
+        assert_eq!("md", registry.get("markdown").unwrap().extension());
+
+        let qs = questionnaire_test();
+        let metadata = metadata_test();
+        let mut out = Vec::new();
+        registry.export("markdown", &ExportSource::new(&qs, &metadata), &ExportOptions::default(), &mut out).unwrap();
+
+        assert_eq!("# QC Report\n", String::from_utf8(out).unwrap());
+    }
+}
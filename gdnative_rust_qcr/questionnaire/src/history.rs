@@ -0,0 +1,113 @@
+/*
+ * Audit trail of the edits made to unit forms.
+ *
+ * Every change keeps who made it, when, and the value before and after,
+ * as the text shown in reports.
+ */
+use crate::error::QuestionnaireError;
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum FormField {
+    // Status or chosen response.
+    Answer,
+    Notes,
+    Value,
+    Attachment,
+}
+
+impl Display for FormField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormField::Answer => write!(f, "answer"),
+            FormField::Notes => write!(f, "notes"),
+            FormField::Value => write!(f, "value"),
+            FormField::Attachment => write!(f, "attachment"),
+        }
+    }
+}
+
+impl FromStr for FormField {
+    type Err = QuestionnaireError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "answer"     => Ok(FormField::Answer),
+            "notes"      => Ok(FormField::Notes),
+            "value"      => Ok(FormField::Value),
+            "attachment" => Ok(FormField::Attachment),
+            _            => Err(QuestionnaireError::Unknown { kind: "form field", value: s.to_string() })
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FormChange {
+    pub form_id: u64,
+    // Seconds since the Unix epoch.
+    pub timestamp: i64,
+    pub engineer: String,
+    pub field: FormField,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl FormChange {
+    pub fn new(form_id: u64, timestamp: i64, engineer: String, field: FormField, old_value: String, new_value: String) -> Self {
+        FormChange { form_id, timestamp, engineer, field, old_value, new_value }
+    }
+
+    // UTC, as "YYYY-MM-DD HH:MM:SS".
+    pub fn time_text(&self) -> String {
+        format_timestamp(self.timestamp)
+    }
+}
+
+pub fn now() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+
+    // Civil date from the number of days since 1970-01-01, proleptic Gregorian calendar.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_from_display() {
+        for field in [FormField::Answer, FormField::Notes, FormField::Value, FormField::Attachment] {
+            assert_eq!(Ok(field), field.to_string().parse::<FormField>());
+        }
+    }
+
+    #[test]
+    fn format_timestamp() {
+        assert_eq!("1970-01-01 00:00:00", super::format_timestamp(0));
+        assert_eq!("2000-02-29 12:30:05", super::format_timestamp(951827405));
+        assert_eq!("2024-12-31 23:59:59", super::format_timestamp(1735689599));
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Self-contained HTML rendering of a report, to be emailed or published.
+ *
+ * Styles are inlined in the page and the chart is an inline SVG, so the
+ * file does not depend on anything else.
+ */
+use crate::questionnaire::Questionnaire;
+use crate::pdf::ReportMetadata;
+use crate::json::status_code;
+use crate::scoring::{Score, StatusCounts};
+
+use std::error::Error;
+use std::fs;
+
+pub trait HTMLWrite {
+    // *chart* is an SVG document, placed as is after the summary.
+    fn write_html(&self, file_path: String, metadata: &ReportMetadata, chart: Option<&str>) -> Result<(), Box<dyn Error>>;
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #999; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+td.status { font-weight: bold; text-align: center; }
+td.ok { background: #c8e6c9; }
+td.no { background: #ffcdd2; }
+td.na { background: #e0e0e0; }
+td.unanswered { background: #fff3c4; }
+.chart svg { max-width: 100%; height: auto; }
+";
+
+impl HTMLWrite for Questionnaire {
+    fn write_html(&self, file_path: String, metadata: &ReportMetadata, chart: Option<&str>) -> Result<(), Box<dyn Error>> {
+        fs::write(file_path, self.to_html(metadata, chart))?;
+
+        Ok(())
+    }
+}
+
+impl Questionnaire {
+    pub fn to_html(&self, metadata: &ReportMetadata, chart: Option<&str>) -> String {
+        let score = self.score();
+        let mut html = String::new();
+
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(&metadata.title), STYLE));
+        html.push_str(&format!("<h1>{}</h1>\n", escape(&metadata.title)));
+
+        // Header
+        html.push_str("<table>\n");
+        let mut rows = vec![
+            ("Engineer", metadata.header.engineer.clone()),
+            ("Job", metadata.header.job.clone()),
+            ("Job type", self.get_job().get_title()),
+            ("Date", metadata.date()),
+            ("Review", self.review().get_state().to_string()),
+        ];
+        if let Some(reviewer) = self.review().get_reviewer() {
+            rows.push(("Reviewer", reviewer.clone()));
+        }
+        for (label, value) in rows {
+            html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape(&value)));
+        }
+        html.push_str("</table>\n");
+
+        // Summary
+        html.push_str("<h2>Summary</h2>\n<table>\n");
+        html.push_str("<tr><th>Section</th><th>OK</th><th>NO</th><th>N/A</th><th>Open</th><th>Compliance</th></tr>\n");
+        for section in score.sections.iter() {
+            html.push_str(&counts_row(&escape(&section.title), &section.counts, &section.score));
+        }
+        html.push_str(&counts_row("<strong>Total</strong>", &score.counts, &score.overall));
+        html.push_str("</table>\n");
+
+        let summary = match self.verdict() {
+            Some(verdict) => format!("Score: {} - {}", score.overall, verdict),
+            None => format!("Score: {}", score.overall),
+        };
+        html.push_str(&format!("<p><strong>{}</strong></p>\n", summary));
+
+        if let Some(chart) = chart {
+            html.push_str(&format!("<div class=\"chart\">\n{}\n</div>\n", chart));
+        }
+
+        // Results
+        html.push_str("<h2>Results</h2>\n");
+        let forms = self.visible_forms();
+
+        for section in score.sections.iter() {
+            html.push_str(&format!("<h3>{}</h3>\n<table>\n", escape(&section.title)));
+            html.push_str("<tr><th>Specification</th><th>Notes</th><th>Value</th><th>Status</th><th>Attachments</th></tr>\n");
+
+            for (_form_id, form) in forms.iter().filter(|(_, form)| form.get_section_id().primitive() == section.section_id) {
+                let specification = match self.get_question(form.get_section_id(), form.get_question_id()) {
+                    Ok(question) => question.get_title(),
+                    Err(_) => String::new(),
+                };
+
+                let attachments = form.get_attachments().iter().map(|a| escape(&a.file_name)).collect::<Vec<String>>().join("<br>");
+
+                html.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"status {}\">{}</td><td>{}</td></tr>\n",
+                                       escape(&specification),
+                                       escape(&form.get_notes()),
+                                       escape(&self.form_value_text(form)),
+                                       status_code(form.get_status()),
+                                       escape(&form.get_label()),
+                                       attachments));
+            }
+
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn counts_row(title: &str, counts: &StatusCounts, score: &Score) -> String {
+    format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            title, counts.ok, counts.no, counts.na, counts.unanswered, score)
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::document::ReportHeader;
+    use crate::job::Job;
+    use crate::questionnaire::QuestionStatus;
+    use std::collections::HashMap;
+
+    #[test]
+    fn escape_markup() {
+        assert_eq!("&lt;b&gt;Tom &amp; Jerry&#39;s &quot;rig&quot;&lt;/b&gt;", escape("<b>Tom & Jerry's \"rig\"</b>"));
+    }
+
+    #[test]
+    fn html_report() {
+        let mut section = Section::new(0, String::from("Cover <Page>"), String::new(), HashMap::new());
+        section.add_question(Question::new(Id::<Question>::new(0), String::from("Title"), String::new()));
+        let mut job = Job::new(0, String::from("Cementing"), String::new(), HashMap::new());
+        job.add_section(section);
+
+        let mut qs = Questionnaire::new(job);
+        qs.update_form_status(0, QuestionStatus::NO).unwrap();
+        qs.update_form_notes(0, String::from("Missing")).unwrap();
+
+        let metadata = ReportMetadata::new(String::from("QC Report"), ReportHeader::new(String::from("J. Doe"), String::from("Well 12"), 3, 7, 2023));
+        let html = qs.to_html(&metadata, Some("<svg></svg>"));
+
+        assert!(html.contains("<h3>Cover &lt;Page&gt;</h3>"));
+        assert!(html.contains("<td class=\"status no\">NO</td>"));
+        assert!(html.contains("<div class=\"chart\">\n<svg></svg>"));
+        assert!(html.contains("<td>2023-07-03</td>"));
+    }
+}
@@ -0,0 +1,259 @@
+/*
+ * Import of a questionnaire CSV filled in a spreadsheet, as written by *CSVWrite*.
+ *
+ * Rows are matched to the forms by their section and specification ids. When the
+ * ids are missing or unknown, e.g. rows added by hand, the names are used instead.
+ * Statuses and notes of the matched rows are applied, other columns are ignored.
+ * Rows that can not be matched or applied are reported, the rest is still imported.
+ */
+use crate::questionnaire::{Questionnaire, QuestionStatus, UnitForm};
+use crate::error::QuestionnaireError;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::Read;
+
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportRecord {
+    #[serde(default)]
+    section_id: Option<u64>,
+    #[serde(default)]
+    specification_id: Option<u64>,
+    #[serde(default)]
+    section_name: String,
+    #[serde(default)]
+    specification_content: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportIssue {
+    // Line of the file, the header is line 1.
+    pub line: u64,
+    pub reason: String,
+}
+
+impl Display for ImportIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ImportReport {
+    // Rows matched to a form and applied.
+    pub applied: usize,
+    pub unmatched: Vec<ImportIssue>,
+    pub invalid: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    pub fn is_clean(&self) -> bool {
+        self.unmatched.is_empty() && self.invalid.is_empty()
+    }
+}
+
+pub fn import_csv<R: Read>(questionnaire: &mut Questionnaire, reader: R) -> Result<ImportReport, Box<dyn Error>> {
+    if questionnaire.review().is_locked() {
+        return Err(QuestionnaireError::ReportLocked.into());
+    }
+
+    let mut rdr = csv::Reader::from_reader(reader);
+    let headers = rdr.headers()?.clone();
+
+    let mut report = ImportReport::default();
+    // Form id -> line that filled it.
+    let mut imported: HashMap<u64, u64> = HashMap::new();
+
+    for result in rdr.records() {
+        let record = result?;
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        let row: ImportRecord = match record.deserialize(Some(&headers)) {
+            Ok(row) => row,
+            Err(e) => {
+                report.invalid.push(ImportIssue { line, reason: e.to_string() });
+                continue;
+            }
+        };
+
+        let form_id = match find_form(questionnaire, &row) {
+            Ok(form_id) => form_id,
+            Err(reason) => {
+                report.unmatched.push(ImportIssue { line, reason: reason.to_string() });
+                continue;
+            }
+        };
+
+        if let Some(first) = imported.get(&form_id) {
+            report.invalid.push(ImportIssue { line, reason: format!("Specification already imported from line {}", first) });
+            continue;
+        }
+
+        match apply(questionnaire, form_id, &row) {
+            Ok(()) => {
+                imported.insert(form_id, line);
+                report.applied += 1;
+            },
+            Err(reason) => report.invalid.push(ImportIssue { line, reason: reason.to_string() }),
+        }
+    }
+
+    Ok(report)
+}
+
+fn find_form(questionnaire: &Questionnaire, row: &ImportRecord) -> Result<u64, &'static str> {
+    if let (Some(section_id), Some(specification_id)) = (row.section_id, row.specification_id) {
+        let found = questionnaire.forms.values().find(|form| {
+            form.get_section_id().primitive() == section_id && form.get_question_id().primitive() == specification_id
+        });
+
+        if let Some(form) = found {
+            return Ok(form.get_id());
+        }
+    }
+
+    let section = row.section_name.trim();
+    let specification = row.specification_content.trim();
+
+    if section.is_empty() || specification.is_empty() {
+        return Err("No specification with the given ids");
+    }
+
+    let found: Vec<&UnitForm> = questionnaire.forms.values().filter(|form| {
+        let section_title = questionnaire.get_section(form.get_section_id()).map(|s| s.get_title());
+        let specification_title = questionnaire.get_question(form.get_section_id(), form.get_question_id()).map(|q| q.get_title());
+
+        section_title.is_ok_and(|title| title.trim() == section) && specification_title.is_ok_and(|title| title.trim() == specification)
+    }).collect();
+
+    match found.len() {
+        0 => Err("No specification with the given section and specification names"),
+        1 => Ok(found[0].get_id()),
+        _ => Err("Several specifications with the given section and specification names"),
+    }
+}
+
+// Nothing is applied when the status is not valid for the form.
+fn apply(questionnaire: &mut Questionnaire, form_id: u64, row: &ImportRecord) -> Result<(), QuestionnaireError> {
+    let status = row.status.trim();
+    let form = &questionnaire.forms[&form_id];
+
+    if status != form.get_label() {
+        if status.is_empty() {
+            questionnaire.update_form_status(form_id, QuestionStatus::Unanswered)?;
+        } else {
+            // Options are looked up by label, as exported, or by code.
+            let code = questionnaire.form_scale(form_id)?.options().iter()
+                .find(|option| option.label.eq_ignore_ascii_case(status) || option.code.eq_ignore_ascii_case(status))
+                .map(|option| option.code.clone());
+
+            match code {
+                Some(code) => questionnaire.update_form_response(form_id, &code)?,
+                None => {
+                    let status = status.to_uppercase().parse::<QuestionStatus>()?;
+                    questionnaire.update_form_status(form_id, status)?;
+                },
+            }
+        }
+    }
+
+    if row.notes != questionnaire.forms[&form_id].get_notes() {
+        questionnaire.update_form_notes(form_id, row.notes.clone())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+    use crate::scale::{ResponseOption, ResponseScale};
+
+    fn questionnaire_test() -> Questionnaire {
+        let mut section = Section::new(0, String::from("Cover"), String::new(), HashMap::new());
+        section.add_question(Question::new(Id::<Question>::new(0), String::from("Title"), String::new()));
+        section.add_question(Question::new(Id::<Question>::new(1), String::from("Logo"), String::new()));
+
+        let mut rated = Question::new(Id::<Question>::new(2), String::from("Layout"), String::new());
+        rated.set_scale(Some(ResponseScale::new(1, String::from("Severity"), vec![
+            ResponseOption::new(String::from("P"), String::from("Pass"), QuestionStatus::OK),
+            ResponseOption::new(String::from("MAJ"), String::from("Major"), QuestionStatus::NO),
+        ])));
+        section.add_question(rated);
+
+        let mut job = Job::new(0, String::from("Cementing"), String::new(), HashMap::new());
+        job.add_section(section);
+
+        Questionnaire::new(job)
+    }
+
+    fn form(qs: &Questionnaire, question_id: u64) -> &UnitForm {
+        qs.all_forms().into_iter().find(|(_, form)| form.get_question_id().primitive() == question_id).unwrap().1
+    }
+
+    #[test]
+    fn import_rows() {
+        let mut qs = questionnaire_test();
+        let csv = "section_id,specification_id,section_name,specification_content,notes,status\n\
+                   0,0,Cover,Title,Misspelled,no\n\
+                   ,,Cover,Logo,,N/A\n\
+                   0,2,Cover,Layout,,Major\n";
+
+        let report = import_csv(&mut qs, csv.as_bytes()).unwrap();
+
+        assert!(report.is_clean());
+        assert_eq!(3, report.applied);
+        assert_eq!(QuestionStatus::NO, form(&qs, 0).get_status());
+        assert_eq!("Misspelled", form(&qs, 0).get_notes());
+        assert_eq!(QuestionStatus::NA, form(&qs, 1).get_status());
+        assert_eq!("Major", form(&qs, 2).get_label());
+    }
+
+    #[test]
+    fn report_bad_rows() {
+        let mut qs = questionnaire_test();
+        let csv = "section_id,specification_id,section_name,specification_content,notes,status\n\
+                   7,7,Cover,Missing,,OK\n\
+                   0,0,Cover,Title,,Maybe\n\
+                   x,1,Cover,Logo,,OK\n\
+                   0,1,Cover,Logo,,OK\n\
+                   0,1,Cover,Logo,,NO\n";
+
+        let report = import_csv(&mut qs, csv.as_bytes()).unwrap();
+
+        assert_eq!(1, report.applied);
+        assert_eq!(vec![2], report.unmatched.iter().map(|issue| issue.line).collect::<Vec<u64>>());
+        assert_eq!(vec![3, 4, 6], report.invalid.iter().map(|issue| issue.line).collect::<Vec<u64>>());
+        assert_eq!(QuestionStatus::Unanswered, form(&qs, 0).get_status());
+        assert_eq!(QuestionStatus::OK, form(&qs, 1).get_status());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut filled = questionnaire_test();
+        let id = form(&filled, 2).get_id();
+        filled.update_form_response(id, "MAJ").unwrap();
+        filled.update_form_notes(id, String::from("Cluttered")).unwrap();
+
+        let file_path = std::env::temp_dir().join("questionnaire_import_round_trip.csv");
+        crate::CSVWrite::write_csv(&filled, file_path.to_string_lossy().to_string()).unwrap();
+
+        let mut qs = questionnaire_test();
+        let report = qs.import_csv(file_path.to_string_lossy().to_string()).unwrap();
+        std::fs::remove_file(file_path).unwrap();
+
+        assert!(report.is_clean());
+        assert_eq!("Major", form(&qs, 2).get_label());
+        assert_eq!("Cluttered", form(&qs, 2).get_notes());
+        assert!(qs.diff(&filled).is_empty());
+    }
+}
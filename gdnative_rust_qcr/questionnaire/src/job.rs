@@ -1,15 +1,34 @@
-use crate::data::{Id, Section, Question};
+use crate::data::{Id, Section, Question, QuestionKind};
+use crate::scale::ResponseScale;
+use crate::rules::Rule;
+use crate::error::QuestionnaireError;
 
 use std::collections::HashMap;
 
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug)]
+
+// Questions without a section are grouped under this synthetic section.
+// Any u64 is a valid section id, the largest database id is used so it still fits in SQLite and Godot integers.
+pub const GENERAL_SECTION_ID: u64 = i64::MAX as u64;
+pub const GENERAL_SECTION_TITLE: &str = "General";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
     id: Id<Job>,
     title: String,
     description: String,
     pub sections: HashMap<u64,Section>,
-    pub orphaned_specifications: HashMap<u64, Question>
+    pub orphaned_specifications: HashMap<u64, Question>,
+    // Scale used by every question that does not define its own.
+    #[serde(default)]
+    scale: ResponseScale,
+    // Minimum score, in percent, for a report to pass.
+    #[serde(default)]
+    pass_threshold: Option<f64>,
+    // Skip logic between the questions of the job.
+    #[serde(default)]
+    rules: Vec<Rule>,
 }
 
 impl PartialEq for Job {
@@ -37,86 +56,197 @@ impl Job {
             title,
             description,
             sections,
-            orphaned_specifications: HashMap::new()
+            orphaned_specifications: HashMap::new(),
+            scale: ResponseScale::default(),
+            pass_threshold: None,
+            rules: Vec::new(),
         }
     }
+    pub fn get_id(&self) -> Id<Job> {
+        self.id.clone()
+    }
+
+    pub fn get_title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn get_rules(&self) -> &Vec<Rule> {
+        &self.rules
+    }
+
+    pub fn set_pass_threshold(&mut self, pass_threshold: Option<f64>) {
+        self.pass_threshold = pass_threshold;
+    }
+
+    pub fn get_pass_threshold(&self) -> Option<f64> {
+        self.pass_threshold
+    }
+
+    pub fn set_scale(&mut self, scale: ResponseScale) {
+        self.scale = scale;
+    }
+
+    pub fn get_scale(&self) -> &ResponseScale {
+        &self.scale
+    }
+
+    // Scale the question is answered with.
+    pub fn scale_for(&self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<&ResponseScale, QuestionnaireError> {
+        let question = self.get_section(s_id)?.get_question(q_id)?;
+
+        Ok(question.get_scale().unwrap_or(&self.scale))
+    }
+
+    // Sets the scale of a question wherever it appears in the job, orphaned or not.
+    pub fn set_question_scale(&mut self, q_id: &Id<Question>, scale: Option<ResponseScale>) {
+        for (_, section) in self.sections.iter_mut() {
+            if let Ok(question) = section.get_mut_question(q_id) {
+                question.set_scale(scale.clone());
+            }
+        }
+
+        if let Some(question) = self.orphaned_specifications.get_mut(&q_id.primitive()) {
+            question.set_scale(scale);
+        }
+    }
+
+    pub fn set_question_weight(&mut self, q_id: &Id<Question>, weight: u32) {
+        for (_, section) in self.sections.iter_mut() {
+            if let Ok(question) = section.get_mut_question(q_id) {
+                question.set_weight(weight);
+            }
+        }
+
+        if let Some(question) = self.orphaned_specifications.get_mut(&q_id.primitive()) {
+            question.set_weight(weight);
+        }
+    }
+
+    pub fn set_question_kind(&mut self, q_id: &Id<Question>, kind: QuestionKind) {
+        for (_, section) in self.sections.iter_mut() {
+            if let Ok(question) = section.get_mut_question(q_id) {
+                question.set_kind(kind.clone());
+            }
+        }
+
+        if let Some(question) = self.orphaned_specifications.get_mut(&q_id.primitive()) {
+            question.set_kind(kind);
+        }
+    }
+
     pub fn add_section(&mut self, section: Section) {
-        if self.has_section(&section.get_id()) == false{
+        if !self.has_section(&section.get_id()) {
             self.sections.insert(section.get_id().primitive(), section);
         }
     }
-    pub fn get_section(&self, id: &Id<Section>) -> Result<&Section, &'static str> {
+    // Adds a section on top of the job template. 
+    // If the job already has the section, only the questions it is missing are added.
+    pub fn merge_section(&mut self, section: Section) {
+        match self.sections.get_mut(&section.get_id().primitive()) {
+            Some(existing) => {
+                for question in section.all_questions().values() {
+                    existing.add_question(question.clone());
+                }
+            },
+            None => {
+                self.sections.insert(section.get_id().primitive(), section);
+            }
+        }
+    }
+
+    pub fn get_section(&self, id: &Id<Section>) -> Result<&Section, QuestionnaireError> {
         let id = id.primitive();
 
         match self.sections.get(&id) {
-            Some(section) => Ok(&section),
-            None      => Err("No section found")
+            Some(section) => Ok(section),
+            None      => Err(QuestionnaireError::MissingSection(id))
         }
 
     }
 
-    pub fn get_mut_section(&mut self, id: &Id<Section>) -> Result<&mut Section, &'static str> {
+    pub fn get_mut_section(&mut self, id: &Id<Section>) -> Result<&mut Section, QuestionnaireError> {
         match self.sections.get_mut(&id.primitive()) {
             Some(section) => Ok(section),
-            None          => Err("No section found")
+            None          => Err(QuestionnaireError::MissingSection(id.primitive()))
         }
     }
 
-    pub fn remove_section(&mut self, id: Id<Section>) -> Result<(), &'static str> {
+    pub fn remove_section(&mut self, id: Id<Section>) -> Result<(), QuestionnaireError> {
         match self.sections.remove(&id.primitive()) {
             Some(_) => Ok(()),
-            None          => Err("No section found that could be removed")
+            None          => Err(QuestionnaireError::MissingSection(id.primitive()))
         }
     }
 
-    pub fn get_question(&self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<&Question, &'static str> {
-        self.get_section(&s_id).unwrap().get_question(q_id)
+    pub fn get_question(&self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<&Question, QuestionnaireError> {
+        self.get_section(s_id)?.get_question(q_id)
     }
 
-    pub fn add_question(&mut self, s_id: Id<Section>, q: Question) -> Result<(), &'static str> {
-        let mut section: &mut Section = self.get_mut_section(&s_id).unwrap();
+    pub fn add_question(&mut self, s_id: Id<Section>, q: Question) -> Result<(), QuestionnaireError> {
+        let section: &mut Section = self.get_mut_section(&s_id)?;
+
+        if section.has_question(&q.get_id()) {
+            return Err(QuestionnaireError::DuplicateQuestion { section_id: s_id.primitive(), question_id: q.get_id().primitive() });
+        }
+
         section.add_question(q);
 
         Ok(())
     }
 
-    pub fn get_orphaned_question(&self, q_id: &Id<Question>) -> Result<&Question, &'static str> {
+    pub fn get_orphaned_question(&self, q_id: &Id<Question>) -> Result<&Question, QuestionnaireError> {
         match self.orphaned_specifications.get(&q_id.primitive()) {
             Some(spec) => {
                 Ok(spec)
             },
             None => {
-                Err("No orphaned question")
+                Err(QuestionnaireError::MissingOrphanedQuestion(q_id.primitive()))
             }
         }
 
 
     }
 
-    pub fn add_orphaned_question(&mut self, question: Question) -> Result<(), &'static str> {
-        self.orphaned_specifications.insert(question.get_id().primitive(), question);
+    // Orphaned questions end up in the general section.
+    pub fn add_orphaned_question(&mut self, question: Question) -> Result<(), QuestionnaireError> {
+        let id = question.get_id().primitive();
+
+        if self.orphaned_specifications.contains_key(&id) {
+            return Err(QuestionnaireError::DuplicateQuestion { section_id: GENERAL_SECTION_ID, question_id: id });
+        }
+
+        self.orphaned_specifications.insert(id, question);
 
         Ok(())
     }
 
-    pub fn remove_orphaned_question(&mut self, q_id: &Id<Question>) -> Result<(), &'static str> {
+    pub fn remove_orphaned_question(&mut self, q_id: &Id<Question>) -> Result<(), QuestionnaireError> {
         match self.orphaned_specifications.remove(&q_id.primitive()) {
             Some(_) => Ok(()),
-            None    => Err("No orphaned question to remove")
+            None    => Err(QuestionnaireError::MissingOrphanedQuestion(q_id.primitive()))
         }
     }
 
-    fn remove_question(&mut self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<() , &'static str>{
-        if let Ok(s) = self.get_mut_section(s_id) {
-            if let Ok(_) = s.get_question(q_id) {
+    // Section holding all orphaned questions, if there are any.
+    pub fn general_section(&self) -> Option<Section> {
+        if self.orphaned_specifications.is_empty() {
+            return None;
+        }
 
-                 s.remove_question(q_id)
-            } else {
-                Err("No question")
-            }
-        } else {
-            Err("No section")
+        let mut section = Section::new(GENERAL_SECTION_ID, String::from(GENERAL_SECTION_TITLE), String::from("Specifications without a section"), HashMap::new());
+        for (_, question) in self.orphaned_specifications.iter() {
+            section.add_question(question.clone());
         }
+
+        Some(section)
+    }
+
+    fn remove_question(&mut self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<() , QuestionnaireError>{
+        self.get_mut_section(s_id)?.remove_question(q_id)
     }
 
     pub fn has_section(&self, s_id: &Id<Section>) -> bool {
@@ -171,6 +301,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn add_question_errors() {
+        let mut job = Job::new(0, String::from("Dummy job"), String::from("testing job"), HashMap::new());
+        job.add_section(Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new()));
+
+        let q = Question::new(Id::<Question>::new(3), String::from("Question dummy"), String::from("me"));
+
+        assert_eq!(Err(QuestionnaireError::MissingSection(7)), job.add_question(Id::<Section>::new(7), q.clone()));
+        assert_eq!(Err(QuestionnaireError::MissingSection(7)), job.get_question(&Id::<Section>::new(7), &Id::<Question>::new(3)).map(|_| ()));
+
+        job.add_question(Id::<Section>::new(0), q.clone()).unwrap();
+        assert_eq!(Err(QuestionnaireError::DuplicateQuestion { section_id: 0, question_id: 3 }), job.add_question(Id::<Section>::new(0), q));
+    }
+
     #[test]
     fn remove_question() {
         let s = Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new());
@@ -191,6 +335,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merge_section() {
+        let mut s = Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new());
+        s.add_question(Question::new(Id::<Question>::new(0), String::from("Question dummy"), String::from("me")));
+
+        let mut job = Job::new(0, String::from("Dummy job"), String::from("testing job"), HashMap::new());
+        job.add_section(s);
+
+        // Same section with an extra question, and a new section.
+        let mut same = Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new());
+        same.add_question(Question::new(Id::<Question>::new(0), String::from("Question dummy"), String::from("me")));
+        same.add_question(Question::new(Id::<Question>::new(1), String::from("Question extra"), String::from("me")));
+        let mut hse = Section::new(1, String::from("HSE"), String::from("Additive section"), HashMap::new());
+        hse.add_question(Question::new(Id::<Question>::new(2), String::from("Permit to work"), String::from("me")));
+
+        job.merge_section(same);
+        job.merge_section(hse);
+
+        assert_eq!(2, job.get_section(&Id::<Section>::new(0)).unwrap().all_questions().len());
+        assert!(job.has_section(&Id::<Section>::new(1)));
+    }
+
+    #[test]
+    fn general_section() {
+        let mut job = Job::new(0, String::from("Dummy job"), String::from("testing job"), HashMap::new());
+
+        assert_eq!(None, job.general_section());
+
+        let q = Question::new(Id::<Question>::new(0), String::from("Question dummy"), String::from("me"));
+        job.add_orphaned_question(q).unwrap();
+
+        let general = job.general_section().unwrap();
+        assert_eq!(Id::<Section>::new(GENERAL_SECTION_ID), general.get_id());
+        assert!(general.has_question(&Id::<Question>::new(0)));
+    }
+
+    #[test]
+    fn scale_for() {
+        use crate::questionnaire::QuestionStatus;
+        use crate::scale::{ResponseOption, ResponseScale};
+
+        let rating = ResponseScale::new(1, String::from("Rating"), vec![
+            ResponseOption::new(String::from("1"), String::from("1 - Poor"), QuestionStatus::NO),
+            ResponseOption::new(String::from("5"), String::from("5 - Excellent"), QuestionStatus::OK),
+        ]);
+
+        let rated = Question::new(Id::<Question>::new(1), String::from("Question rated"), String::from("me"));
+
+        let mut s = Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new());
+        s.add_question(Question::new(Id::<Question>::new(0), String::from("Question dummy"), String::from("me")));
+        s.add_question(rated);
+
+        let mut job = Job::new(0, String::from("Dummy job"), String::from("testing job"), HashMap::new());
+        job.add_section(s);
+        job.set_question_scale(&Id::<Question>::new(1), Some(rating.clone()));
+
+        let section = Id::<Section>::new(0);
+        assert_eq!(&ResponseScale::default(), job.scale_for(&section, &Id::<Question>::new(0)).unwrap());
+        assert_eq!(&rating, job.scale_for(&section, &Id::<Question>::new(1)).unwrap());
+        assert!(job.scale_for(&section, &Id::<Question>::new(2)).is_err());
+    }
+
     #[test]
     fn has_section() {
         let s = Section::new(0, String::from("Dummy section"), String::from("Testing section"), HashMap::new());
@@ -0,0 +1,222 @@
+/*
+ * JSON export of a report for downstream tools.
+ *
+ * Unlike the CSV, which holds display strings, the export uses ids and stable codes.
+ * Fields are only added within a schema version; renaming or removing one bumps it.
+ *
+ * Schema version 1:
+ * {
+ *   "schema_version": 1,
+ *   "header": { "engineer", "job", "date": "YYYY-MM-DD", "job_id", "job_type" },
+ *   "review": { "state": "draft" | "submitted" | "approved" | "returned", "reviewer": string | null },
+ *   "sections": [{
+ *     "id", "title",
+ *     "statistics": { "ok", "no", "percent_compliance" } | null,
+ *     "specifications": [{
+ *       "form_id", "id", "text",
+ *       "status": "ok" | "no" | "na" | "unanswered",
+ *       "response": code of the scale option | null,
+ *       "value": measured value | null, "unit": string | null,
+ *       "notes", "attachments": [file names]
+ *     }]
+ *   }]
+ * }
+ *
+ * Sections and specifications are ordered by id, hidden specifications are left out.
+ * Statistics are null for sections with nothing to score, e.g. only N/A answers.
+ */
+use crate::questionnaire::{Questionnaire, QuestionStatus};
+use crate::document::ReportHeader;
+use crate::JSONWrite;
+
+use std::error::Error;
+use std::fs;
+
+use serde::Serialize;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Compliance of a section, as charted.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct SectionStatistics {
+    // None for sections counted by title only, these are not matched to a section.
+    #[serde(skip)]
+    pub section_id: Option<u64>,
+    #[serde(skip)]
+    pub section: String,
+    pub ok: u64,
+    pub no: u64,
+    pub percent_compliance: u64,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    header: JsonHeader<'a>,
+    review: JsonReview<'a>,
+    sections: Vec<JsonSection<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonHeader<'a> {
+    engineer: &'a str,
+    job: &'a str,
+    date: String,
+    job_id: u64,
+    job_type: String,
+}
+
+#[derive(Serialize)]
+struct JsonReview<'a> {
+    state: String,
+    reviewer: Option<&'a String>,
+}
+
+#[derive(Serialize)]
+struct JsonSection<'a> {
+    id: u64,
+    title: String,
+    statistics: Option<&'a SectionStatistics>,
+    specifications: Vec<JsonSpecification<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSpecification<'a> {
+    form_id: u64,
+    id: u64,
+    text: String,
+    status: &'static str,
+    response: Option<&'a str>,
+    value: Option<f64>,
+    unit: Option<String>,
+    notes: String,
+    attachments: Vec<&'a str>,
+}
+
+pub struct QuestionnaireExport<'a> {
+    questionnaire: &'a Questionnaire,
+    header: &'a ReportHeader,
+    // Matched to the sections by id.
+    statistics: Vec<SectionStatistics>,
+}
+
+impl<'a> QuestionnaireExport<'a> {
+    pub fn new(questionnaire: &'a Questionnaire, header: &'a ReportHeader, statistics: Vec<SectionStatistics>) -> Self {
+        QuestionnaireExport { questionnaire, header, statistics }
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(&self.report())?)
+    }
+
+    fn report(&self) -> JsonReport<'_> {
+        let qs = self.questionnaire;
+        let forms = qs.visible_forms();
+
+        let sections = qs.score().sections.into_iter().map(|section| {
+            let mut specifications: Vec<JsonSpecification> = forms.iter()
+                .filter(|(_, form)| form.get_section_id().primitive() == section.section_id)
+                .map(|(form_id, form)| {
+                    let question = qs.get_question(form.get_section_id(), form.get_question_id()).ok();
+
+                    JsonSpecification {
+                        form_id: **form_id,
+                        id: form.get_question_id().primitive(),
+                        text: question.map(|q| q.get_title()).unwrap_or_default(),
+                        status: status_code(form.get_status()),
+                        response: form.get_response().map(|option| option.code.as_str()),
+                        value: form.get_value(),
+                        unit: question.and_then(|q| q.get_measurement()).map(|m| m.unit.clone()),
+                        notes: form.get_notes(),
+                        attachments: form.get_attachments().iter().map(|a| a.file_name.as_str()).collect(),
+                    }
+                }).collect();
+            specifications.sort_by_key(|specification| specification.id);
+
+            JsonSection {
+                id: section.section_id,
+                statistics: self.statistics.iter().find(|s| s.section_id == Some(section.section_id)),
+                title: section.title,
+                specifications,
+            }
+        }).collect();
+
+        JsonReport {
+            schema_version: SCHEMA_VERSION,
+            header: JsonHeader {
+                engineer: &self.header.engineer,
+                job: &self.header.job,
+                date: format!("{:04}-{:02}-{:02}", self.header.year, self.header.month, self.header.day),
+                job_id: qs.get_job().get_id().primitive(),
+                job_type: qs.get_job().get_title(),
+            },
+            review: JsonReview {
+                state: qs.review().get_state().to_string(),
+                reviewer: qs.review().get_reviewer(),
+            },
+            sections,
+        }
+    }
+}
+
+impl<'a> JSONWrite for QuestionnaireExport<'a> {
+    fn write_json(&self, file_path: String) -> Result<(), Box<dyn Error>> {
+        fs::write(file_path, self.to_json()?)?;
+
+        Ok(())
+    }
+}
+
+// Stable code of a status, also used as CSS class by the HTML export.
+pub(crate) fn status_code(status: QuestionStatus) -> &'static str {
+    match status {
+        QuestionStatus::OK => "ok",
+        QuestionStatus::NO => "no",
+        QuestionStatus::NA => "na",
+        QuestionStatus::Unanswered => "unanswered",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+    use std::collections::HashMap;
+
+    #[test]
+    fn export_schema() {
+        let mut cover = Section::new(0, String::from("Cover"), String::new(), HashMap::new());
+        cover.add_question(Question::new(Id::<Question>::new(0), String::from("Title"), String::new()));
+        cover.add_question(Question::new(Id::<Question>::new(1), String::from("Logo"), String::new()));
+        let mut annex = Section::new(1, String::from("Annex"), String::new(), HashMap::new());
+        annex.add_question(Question::new(Id::<Question>::new(2), String::from("Tables"), String::new()));
+
+        let mut job = Job::new(4, String::from("Cementing"), String::new(), HashMap::new());
+        job.add_section(cover);
+        job.add_section(annex);
+
+        let mut qs = Questionnaire::new(job);
+        let form_id = *qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == 1).unwrap().0;
+        qs.update_form_status(form_id, QuestionStatus::NO).unwrap();
+        qs.update_form_notes(form_id, String::from("Blurry")).unwrap();
+
+        let header = ReportHeader::new(String::from("J. Doe"), String::from("Well 12"), 3, 7, 2023);
+        // Statistics of a section renamed since the plot was drawn.
+        let statistics = vec![SectionStatistics { section_id: Some(0), section: String::from("Front"), ok: 0, no: 1, percent_compliance: 0 }];
+        let json: serde_json::Value = serde_json::from_str(&QuestionnaireExport::new(&qs, &header, statistics).to_json().unwrap()).unwrap();
+
+        assert_eq!(SCHEMA_VERSION as u64, json["schema_version"]);
+        assert_eq!("2023-07-03", json["header"]["date"]);
+        assert_eq!(4, json["header"]["job_id"]);
+        assert_eq!("draft", json["review"]["state"]);
+
+        let cover = &json["sections"][0];
+        assert_eq!("Cover", cover["title"]);
+        assert_eq!(1, cover["statistics"]["no"]);
+        assert_eq!("no", cover["specifications"][1]["status"]);
+        assert_eq!("Blurry", cover["specifications"][1]["notes"]);
+        assert_eq!("unanswered", cover["specifications"][0]["status"]);
+        assert!(json["sections"][1]["statistics"].is_null());
+    }
+}
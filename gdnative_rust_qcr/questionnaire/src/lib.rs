@@ -1,9 +1,28 @@
 use std::error::Error;
 
+pub mod error;
 pub mod data;
 pub mod job;
 pub mod questionnaire;
+pub mod document;
+pub mod scale;
+pub mod scoring;
+pub mod rules;
+pub mod attachment;
+pub mod history;
+pub mod review;
+pub mod diff;
+pub mod merge;
+pub mod import;
+pub mod pdf;
+pub mod html;
+pub mod json;
+pub mod export;
 
 pub trait CSVWrite {
     fn write_csv(&self, file_path: String) -> Result<(), Box<dyn Error>>;
 }
+
+pub trait JSONWrite {
+    fn write_json(&self, file_path: String) -> Result<(), Box<dyn Error>>;
+}
@@ -0,0 +1,348 @@
+/*
+ * Merge of two copies of a questionnaire, filled by different engineers.
+ *
+ * The answer (status, response and value) and the notes of a form are merged separately.
+ * A part filled on one side only is taken from that side. A part filled differently
+ * on both sides is a conflict, and keeps our side until it is resolved.
+ * Attachments and change histories are combined, and what the merge changes on
+ * our side is recorded in the history as well. Approved reports can not be merged into.
+ */
+use crate::questionnaire::{Questionnaire, QuestionStatus, UnitForm};
+use crate::history::{FormChange, FormField};
+use crate::error::QuestionnaireError;
+
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeConflict {
+    // Id of the form in the merged questionnaire.
+    pub form_id: u64,
+    pub specification: String,
+    pub answer: bool,
+    pub notes: bool,
+    ours: UnitForm,
+    theirs: UnitForm,
+}
+
+impl MergeConflict {
+    pub fn ours(&self) -> &UnitForm {
+        &self.ours
+    }
+
+    pub fn theirs(&self) -> &UnitForm {
+        &self.theirs
+    }
+}
+
+#[derive(Debug)]
+pub struct MergeResult {
+    merged: Questionnaire,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    pub fn merged(&self) -> &Questionnaire {
+        &self.merged
+    }
+
+    // Conflicts still waiting for a resolution.
+    pub fn conflicts(&self) -> &Vec<MergeConflict> {
+        &self.conflicts
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    // Keeps the conflicting parts of the form from *side*.
+    pub fn resolve(&mut self, form_id: u64, side: Side) -> Result<(), QuestionnaireError> {
+        self.merged.check_editable()?;
+
+        let index = match self.conflicts.iter().position(|conflict| conflict.form_id == form_id) {
+            Some(index) => index,
+            None => return Err(QuestionnaireError::NoConflict(form_id))
+        };
+
+        let conflict = self.conflicts.remove(index);
+        let chosen = match side {
+            Side::Ours => &conflict.ours,
+            Side::Theirs => &conflict.theirs,
+        };
+
+        let form = match self.merged.forms.get_mut(&form_id) {
+            Some(form) => form,
+            None => return Err(QuestionnaireError::MissingForm(form_id))
+        };
+
+        let old = form.clone();
+
+        if conflict.answer {
+            copy_answer(form, chosen);
+        }
+
+        if conflict.notes {
+            form.update_notes(chosen.get_notes());
+        }
+
+        record_changes(&mut self.merged, &old);
+
+        Ok(())
+    }
+
+    pub fn into_questionnaire(self) -> Result<Questionnaire, QuestionnaireError> {
+        if self.is_resolved() {
+            Ok(self.merged)
+        } else {
+            Err(QuestionnaireError::UnresolvedConflicts(self.conflicts.len()))
+        }
+    }
+}
+
+// Both questionnaires must be built from the same job.
+pub fn merge(ours: &Questionnaire, theirs: &Questionnaire) -> Result<MergeResult, QuestionnaireError> {
+    ours.check_editable()?;
+
+    if ours.get_job().get_id() != theirs.get_job().get_id() {
+        return Err(QuestionnaireError::DifferentJobs);
+    }
+
+    // Forms are matched by section and specification, the ids of the copies can differ.
+    let their_forms: HashMap<(u64, u64), &UnitForm> = theirs.forms.values().map(|form| (key(form), form)).collect();
+
+    if their_forms.len() != ours.forms.len() || ours.forms.values().any(|form| !their_forms.contains_key(&key(form))) {
+        return Err(QuestionnaireError::DifferentSpecifications);
+    }
+
+    let mut merged = ours.clone();
+    let mut conflicts = Vec::new();
+
+    for (form_id, form) in merged.forms.iter_mut() {
+        let their_form = their_forms[&key(form)];
+        let our_form = form.clone();
+
+        let answer = match (has_answer(&our_form), has_answer(their_form)) {
+            (false, true) => {
+                copy_answer(form, their_form);
+                false
+            },
+            (true, true) => !same_answer(&our_form, their_form),
+            _ => false,
+        };
+
+        let notes = match (our_form.get_notes().is_empty(), their_form.get_notes().is_empty()) {
+            (true, false) => {
+                form.update_notes(their_form.get_notes());
+                false
+            },
+            (false, false) => our_form.get_notes() != their_form.get_notes(),
+            _ => false,
+        };
+
+        for attachment in their_form.get_attachments() {
+            form.add_attachment(attachment.clone());
+        }
+
+        if answer || notes {
+            let specification = match ours.get_question(form.get_section_id(), form.get_question_id()) {
+                Ok(question) => question.get_title(),
+                Err(_) => String::new(),
+            };
+
+            conflicts.push(MergeConflict { form_id: *form_id, specification, answer, notes, ours: our_form, theirs: their_form.clone() });
+        }
+    }
+
+    conflicts.sort_by_key(|conflict| conflict.form_id);
+
+    // Both trails are kept, their form ids refer to our copy. Changes made before the copies
+    // were split are in both and only kept once.
+    let their_ids: HashMap<u64, u64> = theirs.forms.values()
+        .filter_map(|form| ours.forms.values().find(|our| key(our) == key(form)).map(|our| (form.get_id(), our.get_id())))
+        .collect();
+
+    let mut history = ours.history().clone();
+    for change in theirs.history() {
+        let mut change = change.clone();
+        if let Some(id) = their_ids.get(&change.form_id) {
+            change.form_id = *id;
+        }
+        if !ours.history().iter().any(|our| same_change(our, &change)) {
+            history.push(change);
+        }
+    }
+    history.sort_by_key(|change| change.timestamp);
+    merged.set_history(history);
+
+    for form in ours.forms.values() {
+        record_changes(&mut merged, form);
+    }
+
+    Ok(MergeResult { merged, conflicts })
+}
+
+fn key(form: &UnitForm) -> (u64, u64) {
+    (form.get_section_id().primitive(), form.get_question_id().primitive())
+}
+
+fn has_answer(form: &UnitForm) -> bool {
+    form.get_status() != QuestionStatus::Unanswered || form.get_value().is_some()
+}
+
+fn same_answer(a: &UnitForm, b: &UnitForm) -> bool {
+    a.get_status() == b.get_status() && a.get_response() == b.get_response() && a.get_value() == b.get_value()
+}
+
+fn same_change(a: &FormChange, b: &FormChange) -> bool {
+    a.form_id == b.form_id && a.timestamp == b.timestamp && a.field == b.field
+        && a.old_value == b.old_value && a.new_value == b.new_value
+}
+
+// Records how the merge changed *old*, the form as it was on our side.
+fn record_changes(merged: &mut Questionnaire, old: &UnitForm) {
+    let id = old.get_id();
+    let new = match merged.forms.get(&id) {
+        Some(form) => form.clone(),
+        None => return,
+    };
+
+    merged.record_change(id, FormField::Answer, old.get_label(), new.get_label());
+    let (old_value, new_value) = (merged.form_value_text(old), merged.form_value_text(&new));
+    merged.record_change(id, FormField::Value, old_value, new_value);
+    merged.record_change(id, FormField::Notes, old.get_notes(), new.get_notes());
+
+    for attachment in new.get_attachments() {
+        if !old.get_attachments().iter().any(|a| a.hash == attachment.hash) {
+            merged.record_change(id, FormField::Attachment, String::new(), attachment.file_name.clone());
+        }
+    }
+}
+
+fn copy_answer(form: &mut UnitForm, from: &UnitForm) {
+    form.update_status(from.get_status());
+    form.set_response(from.get_response().cloned());
+    form.update_value(from.get_value());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+
+    fn questionnaire_test() -> Questionnaire {
+        let mut section = Section::new(0, String::from("section 1"), String::new(), HashMap::new());
+        for id in 0..3 {
+            section.add_question(Question::new(Id::<Question>::new(id), format!("question {}", id), String::new()));
+        }
+
+        let mut job = Job::new(0, String::from("job 1"), String::new(), HashMap::new());
+        job.add_section(section);
+
+        Questionnaire::new(job)
+    }
+
+    fn form_id(qs: &Questionnaire, question_id: u64) -> u64 {
+        *qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == question_id).unwrap().0
+    }
+
+    #[test]
+    fn merge_without_conflicts() {
+        let mut ours = questionnaire_test();
+        let mut theirs = ours.clone();
+
+        ours.update_form_status(form_id(&ours, 0), QuestionStatus::OK).unwrap();
+        // Answered a minute before theirs, not a change both copies share.
+        let mut history = ours.history().clone();
+        history[0].timestamp -= 60;
+        ours.set_history(history);
+        theirs.update_form_status(form_id(&theirs, 1), QuestionStatus::NO).unwrap();
+        theirs.update_form_notes(form_id(&theirs, 1), String::from("Cracked")).unwrap();
+        theirs.update_form_status(form_id(&theirs, 0), QuestionStatus::OK).unwrap();
+
+        let result = merge(&ours, &theirs).unwrap();
+        assert!(result.is_resolved());
+
+        let merged = result.into_questionnaire().unwrap();
+        assert_eq!(QuestionStatus::OK, merged.forms[&form_id(&merged, 0)].get_status());
+        assert_eq!(QuestionStatus::NO, merged.forms[&form_id(&merged, 1)].get_status());
+        assert_eq!("Cracked", merged.forms[&form_id(&merged, 1)].get_notes());
+        assert_eq!(QuestionStatus::Unanswered, merged.forms[&form_id(&merged, 2)].get_status());
+
+        // Both trails, then the answer and notes taken from theirs.
+        assert_eq!(6, merged.history().len());
+        let copied = merged.form_history(form_id(&merged, 1));
+        assert_eq!(4, copied.len());
+        assert_eq!(FormField::Answer, copied[2].field);
+        assert_eq!(FormField::Notes, copied[3].field);
+    }
+
+    #[test]
+    fn resolve_conflicts() {
+        let mut ours = questionnaire_test();
+        let mut theirs = ours.clone();
+        let id = form_id(&ours, 2);
+
+        ours.update_form_status(id, QuestionStatus::OK).unwrap();
+        theirs.update_form_status(id, QuestionStatus::NO).unwrap();
+        ours.update_form_notes(id, String::from("Fine")).unwrap();
+        theirs.update_form_notes(id, String::from("Worn thread")).unwrap();
+
+        let mut result = merge(&ours, &theirs).unwrap();
+        assert_eq!(1, result.conflicts().len());
+        assert!(result.conflicts()[0].answer);
+        assert!(result.conflicts()[0].notes);
+        assert_eq!("question 2", result.conflicts()[0].specification);
+
+        // Ours until resolved.
+        assert_eq!(QuestionStatus::OK, result.merged().forms[&id].get_status());
+        assert!(merge(&ours, &theirs).unwrap().into_questionnaire().is_err());
+
+        result.resolve(id, Side::Theirs).unwrap();
+        assert_eq!(Err(QuestionnaireError::NoConflict(id)), result.resolve(id, Side::Ours));
+
+        let merged = result.into_questionnaire().unwrap();
+        assert_eq!(QuestionStatus::NO, merged.forms[&id].get_status());
+        assert_eq!("Worn thread", merged.forms[&id].get_notes());
+        assert_eq!("Worn thread", merged.history().last().unwrap().new_value);
+    }
+
+    #[test]
+    fn merge_shared_history() {
+        let mut ours = questionnaire_test();
+        ours.update_form_status(form_id(&ours, 0), QuestionStatus::OK).unwrap();
+        let mut theirs = ours.clone();
+
+        theirs.update_form_status(form_id(&theirs, 1), QuestionStatus::NO).unwrap();
+
+        let merged = merge(&ours, &theirs).unwrap().into_questionnaire().unwrap();
+        assert_eq!(1, merged.form_history(form_id(&merged, 0)).len());
+        // Their change, then the answer copied from theirs.
+        assert_eq!(2, merged.form_history(form_id(&merged, 1)).len());
+        assert_eq!(3, merged.history().len());
+    }
+
+    #[test]
+    fn merge_into_approved() {
+        let mut ours = questionnaire_test();
+        let theirs = ours.clone();
+
+        ours.submit().unwrap();
+        ours.approve(String::from("A. Smith")).unwrap();
+
+        assert_eq!(QuestionnaireError::ReportLocked, merge(&ours, &theirs).unwrap_err());
+    }
+
+    #[test]
+    fn merge_different_jobs() {
+        let ours = questionnaire_test();
+        let theirs = Questionnaire::new(Job::new(1, String::from("job 2"), String::new(), HashMap::new()));
+
+        assert_eq!(QuestionnaireError::DifferentJobs, merge(&ours, &theirs).unwrap_err());
+    }
+}
@@ -0,0 +1,454 @@
+/*
+ * PDF layout of a report.
+ *
+ * A title page with the header of the report, a summary of the answers, then the
+ * results grouped by section with their subtotals. Attachments and the change
+ * history are optional appendices. Every page has a footer with the page number.
+ *
+ * Without a font family, the DejaVu Sans fonts bundled with the crate are used.
+ */
+use crate::questionnaire::{Questionnaire, PDFable};
+use crate::document::ReportHeader;
+use crate::attachment::AttachmentStore;
+use crate::scoring::{ReportScore, Score, StatusCounts};
+
+use std::error::Error;
+use std::fmt::Display;
+use std::io::Write;
+
+use genpdf::{elements, fonts, render, style, Context, Element, Mm, PageDecorator, Position};
+
+// Widest an attached image is shown, in millimeters.
+const MAX_IMAGE_WIDTH: f64 = 120.0;
+// Width of the chart on the summary page, in millimeters.
+const CHART_WIDTH: f64 = 160.0;
+
+#[derive(Debug)]
+pub enum PdfError {
+    // Font family that could not be loaded, and why.
+    Font(String, genpdf::error::Error),
+    Render(genpdf::error::Error),
+}
+
+impl Display for PdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdfError::Font(family, e) => write!(f, "Failed to load font family {}: {}", family, e),
+            PdfError::Render(e) => write!(f, "Failed to render PDF: {}", e),
+        }
+    }
+}
+
+impl Error for PdfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PdfError::Font(_, e) | PdfError::Render(e) => Some(e),
+        }
+    }
+}
+
+impl From<genpdf::error::Error> for PdfError {
+    fn from(e: genpdf::error::Error) -> Self {
+        PdfError::Render(e)
+    }
+}
+
+// DejaVu Sans, bundled so that reports can be written without any font installed.
+pub fn default_font_family() -> Result<fonts::FontFamily<fonts::FontData>, PdfError> {
+    let load = |data: &[u8]| fonts::FontData::new(data.to_vec(), None)
+        .map_err(|e| PdfError::Font(String::from("DejaVu Sans"), e));
+
+    Ok(fonts::FontFamily {
+        regular: load(include_bytes!("../fonts/DejaVuSans.ttf"))?,
+        bold: load(include_bytes!("../fonts/DejaVuSans-Bold.ttf"))?,
+        italic: load(include_bytes!("../fonts/DejaVuSans-Oblique.ttf"))?,
+        bold_italic: load(include_bytes!("../fonts/DejaVuSans-BoldOblique.ttf"))?,
+    })
+}
+
+// *font_style* is the directory of the fonts and the name of the family, see *genpdf::fonts::from_files*.
+fn font_family(font_style: Option<(String,String)>) -> Result<fonts::FontFamily<fonts::FontData>, PdfError> {
+    match font_style {
+        Some((directory, name)) => fonts::from_files(&directory, &name, None)
+            .map_err(|e| PdfError::Font(format!("{} in {}", name, directory), e)),
+        None => default_font_family(),
+    }
+}
+
+// What the report is about, shown on the title page.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportMetadata {
+    pub title: String,
+    pub header: ReportHeader,
+}
+
+impl ReportMetadata {
+    pub fn new(title: String, header: ReportHeader) -> Self {
+        ReportMetadata { title, header }
+    }
+
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.header.year, self.header.month, self.header.day)
+    }
+}
+
+// Image drawn in memory, 8-bit RGB rows from the top left corner.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RasterImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgb: Vec<u8>,
+}
+
+impl RasterImage {
+    pub fn new(width: u32, height: u32, rgb: Vec<u8>) -> Self {
+        RasterImage { width, height, rgb }
+    }
+
+    fn to_element(&self, width_mm: f64) -> Result<elements::Image, Box<dyn Error>> {
+        let data = match image::RgbImage::from_raw(self.width, self.height, self.rgb.clone()) {
+            Some(data) => data,
+            None => return Err("Image buffer does not match its size".into()),
+        };
+
+        // Placed at 300 dpi by default.
+        let scale = width_mm / (self.width as f64 / 300.0 * 25.4);
+
+        Ok(elements::Image::from_dynamic_image(image::DynamicImage::ImageRgb8(data))?.with_scale(genpdf::Scale::new(scale, scale)))
+    }
+}
+
+#[derive(Default)]
+pub struct PdfOptions<'a> {
+    // Image attachments found in the store are embedded, other files are listed by name.
+    pub attachments: Option<&'a AttachmentStore>,
+    pub history: bool,
+    // Compliance chart, shown on the summary page.
+    pub chart: Option<&'a RasterImage>,
+}
+
+impl PDFable for Questionnaire {
+    fn to_pdf(&self, file_path: String, metadata: &ReportMetadata, font_style: Option<(String,String)>) -> Result<(), PdfError> {
+        self.to_pdf_with_options(file_path, metadata, font_style, &PdfOptions::default())
+    }
+}
+
+impl Questionnaire {
+    pub fn to_pdf_with_options(&self, file_path: String, metadata: &ReportMetadata, font_style: Option<(String,String)>, options: &PdfOptions) -> Result<(), PdfError> {
+        self.pdf_document(metadata, font_style, options)?.render_to_file(file_path)?;
+
+        Ok(())
+    }
+
+    // Same as *to_pdf_with_options*, into any writer.
+    pub fn write_pdf(&self, w: &mut dyn Write, metadata: &ReportMetadata, font_style: Option<(String,String)>, options: &PdfOptions) -> Result<(), PdfError> {
+        self.pdf_document(metadata, font_style, options)?.render(w)?;
+
+        Ok(())
+    }
+
+    fn pdf_document(&self, metadata: &ReportMetadata, font_style: Option<(String,String)>, options: &PdfOptions) -> Result<genpdf::Document, PdfError> {
+        let font_family = font_family(font_style)?;
+        // Create a document and set the default font family
+        let mut doc = genpdf::Document::new(font_family);
+        // Change the default settings
+        doc.set_title(metadata.title.clone());
+        doc.set_page_decorator(ReportPageDecorator::new(format!("{} - {} - {}", metadata.title, metadata.header.job, metadata.date())));
+        // Landscape A4 paper
+        doc.set_paper_size((297,210));
+
+        let score = self.score();
+
+        self.push_title_page(&mut doc, metadata)?;
+        doc.push(elements::PageBreak::new());
+        self.push_summary(&mut doc, &score, options.chart)?;
+        doc.push(elements::PageBreak::new());
+        self.push_sections(&mut doc, &score)?;
+
+        if let Some(store) = options.attachments {
+            self.push_attachments(&mut doc, store);
+        }
+
+        if options.history {
+            self.push_history(&mut doc)?;
+        }
+
+        Ok(doc)
+    }
+
+    fn push_title_page(&self, doc: &mut genpdf::Document, metadata: &ReportMetadata) -> Result<(), PdfError> {
+        doc.push(elements::Break::new(4));
+        doc.push(elements::Paragraph::new(metadata.title.clone())
+            .aligned(genpdf::Alignment::Center)
+            .styled(style::Style::new().bold().with_font_size(24)));
+        doc.push(elements::Break::new(3));
+
+        let mut table = elements::TableLayout::new(vec![1,2]);
+        table.set_cell_decorator(elements::FrameCellDecorator::new(true, true, false));
+
+        let mut rows = vec![
+            ("Engineer", metadata.header.engineer.clone()),
+            ("Job", metadata.header.job.clone()),
+            ("Job type", self.get_job().get_title()),
+            ("Date", metadata.date()),
+            ("Review", self.review().get_state().to_string()),
+        ];
+
+        if let Some(reviewer) = self.review().get_reviewer() {
+            rows.push(("Reviewer", reviewer.clone()));
+        }
+
+        for (label, value) in rows {
+            let mut row = table.row();
+            row.push_element(elements::Paragraph::new(label).styled(style::Style::new().bold()).padded(1));
+            row.push_element(elements::Paragraph::new(value).padded(1));
+            row.push()?;
+        }
+
+        doc.push(table);
+
+        Ok(())
+    }
+
+    fn push_summary(&self, doc: &mut genpdf::Document, score: &ReportScore, chart: Option<&RasterImage>) -> Result<(), PdfError> {
+        doc.push(heading("Summary"));
+
+        let mut table = elements::TableLayout::new(vec![4,1,1,1,1,2]);
+        table.set_cell_decorator(elements::FrameCellDecorator::new(true, true, false));
+
+        push_row(&mut table, &["Section", "OK", "NO", "N/A", "Open", "Compliance"], true)?;
+
+        for section in score.sections.iter() {
+            push_counts(&mut table, &section.title, &section.counts, &section.score, false)?;
+        }
+
+        push_counts(&mut table, "Total", &score.counts, &score.overall, true)?;
+
+        doc.push(table);
+        doc.push(elements::Break::new(1));
+
+        let summary = match self.verdict() {
+            Some(verdict) => format!("Score: {} - {}", score.overall, verdict),
+            None => format!("Score: {}", score.overall),
+        };
+        doc.push(elements::Paragraph::new(summary).styled(style::Style::new().bold()));
+
+        if let Some(chart) = chart {
+            doc.push(elements::Break::new(1));
+
+            match chart.to_element(CHART_WIDTH) {
+                Ok(image) => doc.push(image.with_alignment(genpdf::Alignment::Center)),
+                Err(_) => doc.push(elements::Paragraph::new("(unable to embed chart)")),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_sections(&self, doc: &mut genpdf::Document, score: &ReportScore) -> Result<(), PdfError> {
+        doc.push(heading("Results"));
+
+        let forms = self.visible_forms();
+
+        for section_score in score.sections.iter() {
+            doc.push(elements::Break::new(1));
+            doc.push(elements::Paragraph::new(section_score.title.clone()).styled(style::Style::new().bold().with_font_size(12)));
+
+            let mut table = elements::TableLayout::new(vec![3,3,1,1]);
+            table.set_cell_decorator(elements::FrameCellDecorator::new(true, true, false));
+
+            push_row(&mut table, &["Specification", "Notes", "Value", "Status"], true)?;
+
+            for (_form_id, unit_form) in forms.iter().filter(|(_, form)| form.get_section_id().primitive() == section_score.section_id) {
+                let specification = match self.get_question(unit_form.get_section_id(), unit_form.get_question_id()) {
+                    Ok(question) => question.get_title(),
+                    Err(_) => String::new(),
+                };
+
+                push_row(&mut table, &[&specification, &unit_form.get_notes(), &self.form_value_text(unit_form), &unit_form.get_label()], false)?;
+            }
+
+            doc.push(table);
+
+            let counts = &section_score.counts;
+            let compliance = match section_score.score.percentage() {
+                Some(percentage) => format!("{:.1}% compliant", percentage),
+                None => String::from("not scored"),
+            };
+            doc.push(elements::Paragraph::new(format!("Subtotal: {} OK, {} NO, {} N/A - {}",
+                                                      counts.ok, counts.no, counts.na, compliance)));
+        }
+
+        Ok(())
+    }
+
+    fn push_attachments(&self, doc: &mut genpdf::Document, store: &AttachmentStore) {
+        let forms: Vec<_> = self.visible_forms().into_iter().filter(|(_, form)| !form.get_attachments().is_empty()).collect();
+
+        if forms.is_empty() {
+            return;
+        }
+
+        doc.push(elements::PageBreak::new());
+        doc.push(heading("Attachments"));
+
+        for (form_id, unit_form) in forms {
+            let specification = match self.get_question(unit_form.get_section_id(), unit_form.get_question_id()) {
+                Ok(question) => question.get_title(),
+                Err(_) => String::new(),
+            };
+
+            doc.push(elements::Break::new(1));
+            doc.push(elements::Paragraph::new(format!("Form {}: {}", form_id, specification)).styled(style::Style::new().bold()));
+
+            for attachment in unit_form.get_attachments().iter() {
+                if !attachment.is_image() {
+                    doc.push(elements::Paragraph::new(attachment.file_name.clone()));
+                    continue;
+                }
+
+                match load_image(&store.path(attachment)) {
+                    Ok(image) => doc.push(image),
+                    Err(_) => doc.push(elements::Paragraph::new(format!("{} (unable to embed image)", attachment.file_name))),
+                }
+            }
+        }
+    }
+
+    fn push_history(&self, doc: &mut genpdf::Document) -> Result<(), PdfError> {
+        doc.push(elements::PageBreak::new());
+        doc.push(heading("Change history"));
+
+        if self.history().is_empty() {
+            doc.push(elements::Paragraph::new("No changes recorded"));
+            return Ok(());
+        }
+
+        let mut table = elements::TableLayout::new(vec![2,2,2,1,2,2]);
+        table.set_cell_decorator(elements::FrameCellDecorator::new(true, true, false));
+
+        push_row(&mut table, &["Time (UTC)", "Engineer", "Specification", "Field", "Before", "After"], true)?;
+
+        for change in self.history().iter() {
+            let specification = match self.forms.get(&change.form_id) {
+                Some(form) => match self.get_question(form.get_section_id(), form.get_question_id()) {
+                    Ok(question) => question.get_title(),
+                    Err(_) => String::new(),
+                },
+                None => String::new(),
+            };
+
+            push_row(&mut table, &[&change.time_text(), &change.engineer, &specification, &change.field.to_string(), &change.old_value, &change.new_value], false)?;
+        }
+
+        doc.push(table);
+
+        Ok(())
+    }
+}
+
+fn heading(text: &str) -> impl Element {
+    elements::Paragraph::new(text).styled(style::Style::new().bold().with_font_size(16))
+}
+
+fn push_row(table: &mut elements::TableLayout, cells: &[&str], bold: bool) -> Result<(), PdfError> {
+    let style = if bold { style::Style::new().bold() } else { style::Style::new() };
+
+    let mut row = table.row();
+    for cell in cells {
+        row.push_element(elements::Paragraph::new(*cell).styled(style).padded(1));
+    }
+    row.push()?;
+
+    Ok(())
+}
+
+fn push_counts(table: &mut elements::TableLayout, title: &str, counts: &StatusCounts, score: &Score, bold: bool) -> Result<(), PdfError> {
+    push_row(table, &[title,
+                      &counts.ok.to_string(),
+                      &counts.no.to_string(),
+                      &counts.na.to_string(),
+                      &counts.unanswered.to_string(),
+                      &score.to_string()], bold)
+}
+
+fn load_image(path: &std::path::Path) -> Result<elements::Image, Box<dyn Error>> {
+    // Alpha channels can not be embedded.
+    let data = image::open(path)?;
+    let width_px = image::GenericImageView::width(&data) as f64;
+    let data = image::DynamicImage::ImageRgb8(data.to_rgb8());
+
+    // Images are placed at 300 dpi, large photos are scaled down to fit the page.
+    let width_mm = width_px / 300.0 * 25.4;
+    let scale = if width_mm > MAX_IMAGE_WIDTH { MAX_IMAGE_WIDTH / width_mm } else { 1.0 };
+
+    Ok(elements::Image::from_dynamic_image(data)?.with_scale(genpdf::Scale::new(scale, scale)))
+}
+
+// Margins on every page, and a footer with the page number.
+struct ReportPageDecorator {
+    page: usize,
+    footer: String,
+}
+
+impl ReportPageDecorator {
+    fn new(footer: String) -> Self {
+        ReportPageDecorator { page: 0, footer }
+    }
+}
+
+impl PageDecorator for ReportPageDecorator {
+    fn decorate_page<'a>(&mut self, context: &Context, mut area: render::Area<'a>, style: style::Style) -> Result<render::Area<'a>, genpdf::error::Error> {
+        self.page += 1;
+        area.add_margins(10);
+
+        let footer_style = style.with_font_size(8);
+        let line_height = footer_style.line_height(&context.font_cache);
+        let height = area.size().height - line_height;
+
+        let footer = format!("{} - Page {}", self.footer, self.page);
+        area.print_str(&context.font_cache, Position::new(0, height), footer_style, footer)?;
+
+        // Content stops a little above the footer.
+        area.set_height(height - Mm::from(3));
+
+        Ok(area)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raster_image_size() {
+        assert!(RasterImage::new(2, 1, vec![255; 6]).to_element(10.0).is_ok());
+        assert!(RasterImage::new(2, 2, vec![255; 6]).to_element(10.0).is_err());
+    }
+
+    fn metadata() -> ReportMetadata {
+        ReportMetadata::new(String::from("QC Report"), ReportHeader::new(String::from("J. Doe"), String::from("Well 12"), 3, 7, 2023))
+    }
+
+    #[test]
+    fn default_font() {
+        let file_path = std::env::temp_dir().join("qcr_default_font_test.pdf");
+        let qs = Questionnaire::new(crate::job::Job::new(0, String::from("job 1"), String::new(), std::collections::HashMap::new()));
+
+        qs.to_pdf(file_path.to_string_lossy().to_string(), &metadata(), None).unwrap();
+
+        assert!(std::fs::metadata(&file_path).unwrap().len() > 0);
+        std::fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn missing_font() {
+        let qs = Questionnaire::new(crate::job::Job::new(0, String::from("job 1"), String::new(), std::collections::HashMap::new()));
+        let font_style = Some((String::from("/nonexistent"), String::from("Carlito")));
+
+        match qs.to_pdf(String::from("unused.pdf"), &metadata(), font_style) {
+            Err(PdfError::Font(_, _)) => {},
+            other => panic!("expected a font error, got {:?}", other),
+        }
+    }
+}
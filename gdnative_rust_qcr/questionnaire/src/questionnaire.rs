@@ -1,24 +1,37 @@
 use crate::data::{Id,Question, Section};
 use crate::job::Job;
+use crate::scale::{ResponseOption, ResponseScale};
+use crate::scoring::{self, ReportScore, Verdict};
+use crate::rules::RuleAction;
+use crate::attachment::Attachment;
+use crate::pdf::{ReportMetadata, PdfError};
+use crate::history::{self, FormChange, FormField};
+use crate::review::Review;
+use crate::diff::{self, QuestionnaireDiff};
+use crate::merge::{self, MergeResult};
+use crate::error::QuestionnaireError;
+use crate::import::{self, ImportReport};
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use std::{error::Error,io};
 
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 use crate::CSVWrite;
 
-use genpdf::elements;
-
 pub trait PDFable {
-    fn to_pdf(&self, file_path: String, title: String, font_style: (String, String));
+    // Without *font_style*, the bundled default font family is used.
+    fn to_pdf(&self, file_path: String, metadata: &ReportMetadata, font_style: Option<(String, String)>) -> Result<(), PdfError>;
 }
 
-#[derive(Debug,PartialEq,Clone, Copy)]
+#[derive(Debug,PartialEq,Clone, Copy, Serialize, Deserialize)]
 pub enum QuestionStatus {
     OK,
     NA,
     NO,
+    // Not looked at yet, unlike *NA* which is a deliberate answer.
+    Unanswered,
 }
 
 impl Display for QuestionStatus {
@@ -27,13 +40,29 @@ impl Display for QuestionStatus {
             QuestionStatus::OK => "OK".to_string(),
             QuestionStatus::NA => "N/A".to_string(),
             QuestionStatus::NO => "NO".to_string(),
+            QuestionStatus::Unanswered => "".to_string(),
         };
 
         write!(f,"{}",s)
     }
 }
 
-#[derive(Debug,PartialEq)]
+// Reads back the value written by *Display*.
+impl FromStr for QuestionStatus {
+    type Err = QuestionnaireError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OK"  => Ok(QuestionStatus::OK),
+            "N/A" => Ok(QuestionStatus::NA),
+            "NO"  => Ok(QuestionStatus::NO),
+            ""    => Ok(QuestionStatus::Unanswered),
+            _     => Err(QuestionnaireError::Unknown { kind: "question status", value: s.to_string() })
+        }
+    }
+}
+
+#[derive(Debug,PartialEq, Clone, Serialize, Deserialize)]
 pub struct UnitForm {
     u_id: u64,
 
@@ -42,6 +71,14 @@ pub struct UnitForm {
 
     status: QuestionStatus,
     notes: String,
+    // Option of the response scale that was chosen, it determines *status*.
+    #[serde(default)]
+    response: Option<ResponseOption>,
+    // Entered value of a measurement.
+    #[serde(default)]
+    value: Option<f64>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
 }
 
 impl UnitForm {
@@ -52,9 +89,16 @@ impl UnitForm {
             s_id,
             status,
             notes,
+            response: None,
+            value: None,
+            attachments: Vec::new(),
         }
     }
 
+    pub fn get_id(&self) -> u64 {
+        self.u_id
+    }
+
     pub fn get_question_id(&self) -> &Id<Question> {
         &self.q_id
     }
@@ -67,6 +111,57 @@ impl UnitForm {
         self.status = status;
     }
 
+    pub fn update_response(&mut self, response: ResponseOption) {
+        self.status = response.status;
+        self.response = Some(response);
+    }
+
+    // Unlike *update_response*, leaves the status as it is.
+    pub fn set_response(&mut self, response: Option<ResponseOption>) {
+        self.response = response;
+    }
+
+    pub fn update_value(&mut self, value: Option<f64>) {
+        self.value = value;
+    }
+
+    pub fn get_value(&self) -> Option<f64> {
+        self.value
+    }
+
+    // The same file is only attached once.
+    pub fn add_attachment(&mut self, attachment: Attachment) {
+        if !self.attachments.iter().any(|a| a.hash == attachment.hash) {
+            self.attachments.push(attachment);
+        }
+    }
+
+    pub fn remove_attachment(&mut self, hash: &str) -> Result<(), QuestionnaireError> {
+        match self.attachments.iter().position(|a| a.hash == hash) {
+            Some(index) => {
+                self.attachments.remove(index);
+                Ok(())
+            },
+            None => Err(QuestionnaireError::MissingAttachment { form_id: self.u_id, hash: hash.to_string() })
+        }
+    }
+
+    pub fn get_attachments(&self) -> &Vec<Attachment> {
+        &self.attachments
+    }
+
+    pub fn get_response(&self) -> Option<&ResponseOption> {
+        self.response.as_ref()
+    }
+
+    // Text shown in reports for the answer.
+    pub fn get_label(&self) -> String {
+        match &self.response {
+            Some(response) => response.label.clone(),
+            None => self.status.to_string()
+        }
+    }
+
     pub fn update_notes(&mut self, note: String) {
         self.notes = note;
     }
@@ -74,21 +169,52 @@ impl UnitForm {
     pub fn get_status(&self) -> QuestionStatus {
         self.status
     }
+
+    pub fn get_notes(&self) -> String {
+        self.notes.clone()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Completeness {
+    pub total: usize,
+    pub answered: usize,
+    pub unanswered: usize,
+}
+
+impl Completeness {
+    pub fn is_complete(&self) -> bool {
+        self.unanswered == 0
+    }
 }
 
 // Used for CSV writing of questionnaire
 #[derive(Debug,Serialize)]
 struct UnitFormRecord {
+    // Ids let *import::import_csv* match the rows back to the forms.
+    section_id: u64,
+    specification_id: u64,
     section_name: String,
     specification_content: String,
     notes: String,
-    status: String
+    status: String,
+    value: String,
+    attachments: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Questionnaire {
     job: Job,
-    pub forms: HashMap<u64,UnitForm>,
+    // Edited through the questionnaire only, so approved reports stay locked.
+    pub(crate) forms: HashMap<u64,UnitForm>,
+    // Edits made through the questionnaire, oldest first.
+    #[serde(default)]
+    history: Vec<FormChange>,
+    // Recorded with each edit, taken from the header of the report.
+    #[serde(skip)]
+    engineer: String,
+    #[serde(default)]
+    review: Review,
 }
 
 
@@ -107,7 +233,7 @@ impl Display for Questionnaire {
                 Err(_) => "".to_string()
             };
             
-            let row = format!("{}, {}, {}, {}, {}\n",key, section_string, question_string, val.status, val.notes);
+            let row = format!("{}, {}, {}, {}, {}\n",key, section_string, question_string, val.get_label(), val.notes);
             s.push_str(&row);
         }
 
@@ -118,6 +244,8 @@ impl Display for Questionnaire {
 // TODO: Creation of unit form is not complete.
 impl Questionnaire {
     pub fn new(job: Job) -> Self {
+        let job = Questionnaire::with_general_section(job);
+
         let mut unit_form_id = 0;
         let mut hash_map: HashMap<u64, UnitForm> = HashMap::new();
 
@@ -125,7 +253,7 @@ impl Questionnaire {
             let question_hash = section.all_questions();
 
             for (question_key, _) in question_hash.iter() {
-                let uf = UnitForm::new(unit_form_id, Id::<Question>::new(*question_key), Id::<Section>::new(*section_key), QuestionStatus::NA, String::from(""));
+                let uf = UnitForm::new(unit_form_id, Id::<Question>::new(*question_key), Id::<Section>::new(*section_key), QuestionStatus::Unanswered, String::from(""));
 
                 hash_map.insert(uf.u_id, uf);
                 unit_form_id += 1;
@@ -133,42 +261,252 @@ impl Questionnaire {
 
         }
 
-        Questionnaire { job, forms: hash_map } 
+        Questionnaire { job, forms: hash_map, history: Vec::new(), engineer: String::new(), review: Review::default() } 
          
     }
 
-    pub fn get_question(&self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<&Question, &'static str> {
+    // Rebuilds a questionnaire whose forms were already filled, e.g. from a stored report.
+    pub fn from_forms(job: Job, forms: Vec<UnitForm>) -> Self {
+        let job = Questionnaire::with_general_section(job);
+
+        let mut hash_map: HashMap<u64, UnitForm> = HashMap::new();
+
+        for form in forms {
+            hash_map.insert(form.u_id, form);
+        }
+
+        Questionnaire { job, forms: hash_map, history: Vec::new(), engineer: String::new(), review: Review::default() }
+    }
+
+    // Orphaned questions are answered like any other, under the general section.
+    fn with_general_section(mut job: Job) -> Job {
+        if let Some(general) = job.general_section() {
+            job.merge_section(general);
+        }
+
+        job
+    }
+
+    pub fn get_job(&self) -> &Job {
+        &self.job
+    }
+
+    pub fn get_question(&self, s_id: &Id<Section>, q_id: &Id<Question>) -> Result<&Question, QuestionnaireError> {
             self.job.get_question(s_id, q_id) 
     }
 
-    pub fn get_section(&self, s_id: &Id<Section>) -> Result<&Section, &'static str> {
+    pub fn get_section(&self, s_id: &Id<Section>) -> Result<&Section, QuestionnaireError> {
             self.job.get_section(s_id) 
     }
 
-    pub fn update_form_notes(&mut self, id: u64, note: String) -> Result<(), &'static str> {
+    pub fn review(&self) -> &Review {
+        &self.review
+    }
+
+    // Restores the review of a stored report.
+    pub fn set_review(&mut self, review: Review) {
+        self.review = review;
+    }
+
+    pub fn submit(&mut self) -> Result<(), QuestionnaireError> {
+        self.review.submit()
+    }
+
+    pub fn approve(&mut self, reviewer: String) -> Result<(), QuestionnaireError> {
+        self.review.approve(reviewer)
+    }
+
+    pub fn return_report(&mut self, reviewer: String, comments: String) -> Result<(), QuestionnaireError> {
+        self.review.return_report(reviewer, comments)
+    }
+
+    pub(crate) fn check_editable(&self) -> Result<(), QuestionnaireError> {
+        if self.review.is_locked() {
+            Err(QuestionnaireError::ReportLocked)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_engineer(&mut self, engineer: String) {
+        self.engineer = engineer;
+    }
+
+    pub fn get_engineer(&self) -> &String {
+        &self.engineer
+    }
+
+    pub fn history(&self) -> &Vec<FormChange> {
+        &self.history
+    }
+
+    pub fn form_history(&self, id: u64) -> Vec<&FormChange> {
+        self.history.iter().filter(|change| change.form_id == id).collect()
+    }
+
+    // Restores the trail of a stored report.
+    pub fn set_history(&mut self, history: Vec<FormChange>) {
+        self.history = history;
+    }
+
+    // Edits that leave the value as it was are not recorded.
+    pub(crate) fn record_change(&mut self, id: u64, field: FormField, old_value: String, new_value: String) {
+        if old_value != new_value {
+            self.history.push(FormChange::new(id, history::now(), self.engineer.clone(), field, old_value, new_value));
+        }
+    }
+
+    pub fn update_form_notes(&mut self, id: u64, note: String) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
         match self.forms.get_mut(&id) {
             Some(form) => { 
+                let old = form.get_notes();
                 form.update_notes(note);
+                let new = form.get_notes();
+                self.record_change(id, FormField::Notes, old, new);
                 Ok(())
             },
-            None => Err("Unable to update notes")
+            None => Err(QuestionnaireError::MissingForm(id))
         }
     }
 
-    pub fn update_form_status(&mut self, id: u64, status: QuestionStatus) -> Result<(), &'static str> {
+    // Picks the first option of the scale with the given outcome.
+    pub fn update_form_status(&mut self, id: u64, status: QuestionStatus) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
+        let response = match self.form_scale(id) {
+            Ok(scale) => scale.option_for_status(status).cloned(),
+            Err(_) => None
+        };
+
         match self.forms.get_mut(&id) {
             Some(form) => {
+                let old = form.get_label();
                 form.update_status(status);
+                form.set_response(response);
+                let new = form.get_label();
+                self.record_change(id, FormField::Answer, old, new);
+                Ok(())
+            },
+            None => Err(QuestionnaireError::MissingForm(id))
+        }
+    }
+
+    pub fn update_form_response(&mut self, id: u64, code: &str) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
+        let response = match self.form_scale(id)?.get_option(code) {
+            Some(option) => option.clone(),
+            None => return Err(QuestionnaireError::UnknownResponse { form_id: id, code: code.to_string() })
+        };
+
+        match self.forms.get_mut(&id) {
+            Some(form) => {
+                let old = form.get_label();
+                form.update_response(response);
+                let new = form.get_label();
+                self.record_change(id, FormField::Answer, old, new);
+                Ok(())
+            },
+            None => Err(QuestionnaireError::MissingForm(id))
+        }
+    }
+
+    // The status of a measurement follows from its limits.
+    // The status can still be changed afterwards, e.g. to N/A.
+    pub fn update_form_value(&mut self, id: u64, value: f64) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
+        if value.is_nan() {
+            return Err(QuestionnaireError::InvalidMeasurement);
+        }
+
+        let status = match self.forms.get(&id) {
+            Some(form) => match self.job.get_question(&form.s_id, &form.q_id)?.get_measurement() {
+                Some(measurement) => measurement.evaluate(value),
+                None => return Err(QuestionnaireError::NotAMeasurement(id))
+            },
+            None => return Err(QuestionnaireError::MissingForm(id))
+        };
+
+        let old = self.form_value_text(&self.forms[&id]);
+        self.forms.get_mut(&id).unwrap().update_value(Some(value));
+        let new = self.form_value_text(&self.forms[&id]);
+        self.record_change(id, FormField::Value, old, new);
+
+        self.update_form_status(id, status)?;
+
+        Ok(())
+    }
+
+    // Value with its unit, empty when nothing was measured.
+    pub fn form_value_text(&self, form: &UnitForm) -> String {
+        let unit = match self.job.get_question(&form.s_id, &form.q_id) {
+            Ok(question) => question.get_measurement().map(|m| m.unit.clone()),
+            Err(_) => None
+        };
+
+        match (form.value, unit) {
+            (Some(value), Some(unit)) => format!("{} {}", value, unit),
+            (Some(value), None) => value.to_string(),
+            (None, _) => String::new()
+        }
+    }
+
+    pub fn add_form_attachment(&mut self, id: u64, attachment: Attachment) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
+        match self.forms.get_mut(&id) {
+            Some(form) => {
+                let attached = form.attachments.iter().any(|a| a.hash == attachment.hash);
+                let file_name = attachment.file_name.clone();
+                form.add_attachment(attachment);
+                if !attached {
+                    self.record_change(id, FormField::Attachment, String::new(), file_name);
+                }
                 Ok(())
             },
-            None => Err("Unable to update status")
+            None => Err(QuestionnaireError::MissingForm(id))
         }
     }
 
-    pub fn get_form(&mut self, id: u64) -> Result<&mut UnitForm, &'static str> {
+    pub fn remove_form_attachment(&mut self, id: u64, hash: &str) -> Result<(), QuestionnaireError> {
+        self.check_editable()?;
+
         match self.forms.get_mut(&id) {
+            Some(form) => {
+                let file_name = match form.attachments.iter().find(|a| a.hash == hash) {
+                    Some(attachment) => attachment.file_name.clone(),
+                    None => String::new()
+                };
+                form.remove_attachment(hash)?;
+                self.record_change(id, FormField::Attachment, file_name, String::new());
+                Ok(())
+            },
+            None => Err(QuestionnaireError::MissingForm(id))
+        }
+    }
+
+    pub fn form_attachments(&self, id: u64) -> Result<&Vec<Attachment>, QuestionnaireError> {
+        match self.forms.get(&id) {
+            Some(form) => Ok(form.get_attachments()),
+            None => Err(QuestionnaireError::MissingForm(id))
+        }
+    }
+
+    // Scale the form is answered with.
+    pub fn form_scale(&self, id: u64) -> Result<&ResponseScale, QuestionnaireError> {
+        match self.forms.get(&id) {
+            Some(form) => self.job.scale_for(&form.s_id, &form.q_id),
+            None => Err(QuestionnaireError::MissingForm(id))
+        }
+    }
+
+    pub fn get_form(&self, id: u64) -> Result<&UnitForm, QuestionnaireError> {
+        match self.forms.get(&id) {
             Some(form) => Ok(form),
-            None => Err("No form")
+            None => Err(QuestionnaireError::MissingForm(id))
         }
     }
 
@@ -182,8 +520,115 @@ impl Questionnaire {
         vs
     }
 
-    fn to_unit_records(&self) -> Vec<UnitFormRecord> {
-        let forms = self.all_forms();
+    // Forms that are asked, given the rules of the job and the current answers.
+    pub fn visible_forms(&self) -> Vec<(&u64, &UnitForm)> {
+        self.all_forms().into_iter().filter(|(id, _)| self.is_form_visible(**id)).collect()
+    }
+
+    pub fn is_form_visible(&self, id: u64) -> bool {
+        self.form_visible(id, &mut Vec::new())
+    }
+
+    /*
+     * A form is hidden when a *HideIf* rule holds, or when it has *ShowIf* rules and none of them holds.
+     * A rule only holds if the question it depends on is visible itself.
+     * Rules depending on a question the questionnaire does not have are ignored.
+     */
+    fn form_visible(&self, id: u64, visiting: &mut Vec<u64>) -> bool {
+        let form = match self.forms.get(&id) {
+            Some(form) => form,
+            None => return false
+        };
+
+        // Rules depending on each other in a cycle, stop following them.
+        if visiting.contains(&id) {
+            return true;
+        }
+        visiting.push(id);
+
+        let mut shown: Option<bool> = None;
+        let mut hidden = false;
+
+        for rule in self.job.get_rules().iter().filter(|rule| rule.applies_to(form.s_id.primitive(), form.q_id.primitive())) {
+            let controlling: Vec<u64> = self.forms.values()
+                .filter(|other| other.q_id.primitive() == rule.depends_on)
+                .map(|other| other.u_id)
+                .collect();
+
+            if controlling.is_empty() {
+                continue;
+            }
+
+            let holds = controlling.iter().any(|other| self.forms[other].status == rule.status && self.form_visible(*other, visiting));
+
+            match rule.action {
+                RuleAction::ShowIf => shown = Some(shown.unwrap_or(false) || holds),
+                RuleAction::HideIf => hidden = hidden || holds,
+            }
+        }
+
+        visiting.pop();
+
+        !hidden && shown.unwrap_or(true)
+    }
+
+    // Only visible forms have to be answered.
+    pub fn completeness(&self) -> Completeness {
+        let visible = self.visible_forms();
+        let total = visible.len();
+        let unanswered = visible.iter().filter(|(_, form)| form.status == QuestionStatus::Unanswered).count();
+
+        Completeness {
+            total,
+            answered: total - unanswered,
+            unanswered,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completeness().is_complete()
+    }
+
+    // Ids of the forms that still need an answer, grouped by section.
+    pub fn unanswered_forms(&self) -> HashMap<u64, Vec<u64>> {
+        let mut unanswered: HashMap<u64, Vec<u64>> = HashMap::new();
+
+        for (id, form) in self.visible_forms() {
+            if form.status == QuestionStatus::Unanswered {
+                unanswered.entry(form.s_id.primitive()).or_default().push(*id);
+            }
+        }
+
+        unanswered
+    }
+
+    // What changed from this questionnaire to *newer*.
+    pub fn diff(&self, newer: &Questionnaire) -> QuestionnaireDiff {
+        diff::diff(self, newer)
+    }
+
+    // Combines the answers of another copy of this questionnaire, see *merge::merge*.
+    pub fn merge(&self, theirs: &Questionnaire) -> Result<MergeResult, QuestionnaireError> {
+        merge::merge(self, theirs)
+    }
+
+    // Applies a CSV written by *write_csv* and filled elsewhere, see *import::import_csv*.
+    pub fn import_csv(&mut self, file_path: String) -> Result<ImportReport, Box<dyn Error>> {
+        import::import_csv(self, std::fs::File::open(file_path)?)
+    }
+
+    pub fn score(&self) -> ReportScore {
+        scoring::score(self)
+    }
+
+    // None when the job has no pass threshold, or nothing was scored.
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.job.get_pass_threshold().and_then(|threshold| self.score().verdict(threshold))
+    }
+
+    // Fails on a form whose section or specification the job does not have.
+    fn to_unit_records(&self) -> Result<Vec<UnitFormRecord>, QuestionnaireError> {
+        let forms = self.visible_forms();
 
         // go over forms to create the records froms verions, using jobs
         let mut records = Vec::new();
@@ -194,98 +639,56 @@ impl Questionnaire {
             let section_id = form.get_section_id();
             let specification_id = form.get_question_id();
 
-            let section = self.job.get_section(&section_id).unwrap();
-            let specification = self.job.get_question(&section_id, &specification_id).unwrap();
+            let section = self.job.get_section(section_id)?;
+            let specification = self.job.get_question(section_id, specification_id)?;
 
             let section_name = section.get_title();
             let specification_content = specification.get_title();
 
             let notes = form.notes.clone();
-            let status = form.get_status().to_string();
+            let status = form.get_label();
+            let value = self.form_value_text(form);
+            let attachments = form.attachments.iter().map(|a| a.file_name.clone()).collect::<Vec<String>>().join("; ");
 
             // Build record
             let new_record = UnitFormRecord {
+                section_id: section_id.primitive(),
+                specification_id: specification_id.primitive(),
                 section_name,
                 specification_content,
                 notes,
                 status,
+                value,
+                attachments,
             };
 
             records.push(new_record);
         }
 
-        records
+        Ok(records)
     }
 
-}
-
-impl PDFable for Questionnaire {
-    fn to_pdf(&self, file_path: String, title: String, font_style: (String,String)) {
-                
-        let font_family = genpdf::fonts::from_files(&font_style.0,&font_style.1, None)
-            .expect("Failed to load font family");
-        // Create a document and set the default font family
-        let mut doc = genpdf::Document::new(font_family);
-        // Change the default settings
-        doc.set_title(title);
-
-        let mut decorator = genpdf::SimplePageDecorator::new();
-        decorator.set_margins(10);
-        doc.set_page_decorator(decorator);
-        // Landscape A4 paper
-        doc.set_paper_size((297,210));
-
-
-        // Prepare table
-        let mut table = elements::TableLayout::new(vec![1,1,1,1]);
-        table.set_cell_decorator(elements::FrameCellDecorator::new(true, true, false));
-
-        let unit_forms = self.all_forms();
-
-        // Build rows
-        for (_form_id, unit_form) in unit_forms.iter() {
-            // Acquire information
-            let section_id = unit_form.get_section_id();
-            let specification_id = unit_form.get_question_id();
+    // Same as *write_csv*, into any writer.
+    pub fn write_csv_to(&self, w: &mut dyn io::Write) -> Result<(), Box<dyn Error>> {
+        let unit_records = self.to_unit_records()?;
 
-            // Information needed for report.
-            let section = self.job.get_section(section_id).unwrap();
-            let specification = self.job.get_question(section_id, specification_id).unwrap();
-            let notes = unit_form.notes.clone();
-            let status = unit_form.status;
-
-            let mut row = table.row();
-
-            row.push_element(elements::Paragraph::new(section.get_title()));
-            row.push_element(elements::Paragraph::new(specification.get_title()));
-            row.push_element(elements::Paragraph::new(notes));
-            row.push_element(elements::Paragraph::new(status.to_string()));
-
-            row.push().expect("invalid table row");
+        let mut wtr = csv::Writer::from_writer(w);
 
+        for record in unit_records.iter() {
+            wtr.serialize(record)?;
         }
 
-        doc.push(table);
-
-
-        doc.render_to_file(file_path).expect("Failed to write PDF file");
+        wtr.flush()?;
+        Ok(())
     }
 
-
 }
 
 impl CSVWrite for Questionnaire {
     fn write_csv(&self, file_path: String) -> Result<(), Box<dyn Error>> {
-        let unit_records = self.to_unit_records();
-
-        let mut wtr = csv::Writer::from_path(file_path)?;
+        let mut file = std::fs::File::create(file_path)?;
 
-        for record in unit_records.iter() {
-            wtr.serialize(record)?;
-        }
-
-        wtr.flush()?;
-        Ok(())
+        self.write_csv_to(&mut file)
     }
 }
 
@@ -339,7 +742,7 @@ mod test {
     #[test]
     fn questionnaire_create() {
 
-        let mut qs = questionnaire_test();
+        let qs = questionnaire_test();
 
         if let Err(_) = qs.get_form(0) {
             assert!(false);
@@ -376,7 +779,7 @@ mod test {
 
         let q_string = format!("{}",qs);
 
-        assert_eq!("0, section 1, question 1, N/A, \n",q_string);
+        assert_eq!("0, section 1, question 1, , \n",q_string);
 
     }
 
@@ -386,13 +789,260 @@ mod test {
 
         let q_string = format!("{}",qs);
 
-        assert_eq!("0, section 1, question 1, N/A, \n",q_string);
+        assert_eq!("0, section 1, question 1, , \n",q_string);
         qs.update_form_notes(0,String::from("my update of notes"));
 
         let q_string = format!("{}",qs);
 
-        assert_eq!("0, section 1, question 1, N/A, my update of notes\n",q_string);
+        assert_eq!("0, section 1, question 1, , my update of notes\n",q_string);
+
+    }
+
+    #[test]
+    fn questionnaire_orphaned_questions() {
+        use crate::job::GENERAL_SECTION_ID;
+
+        let mut job = Job::new(0, String::from("job 1"), String::from("my job"), HashMap::new());
+        let question = Question::new(Id::<Question>::new(3),String::from("orphan"), String::from("no section"));
+        job.add_orphaned_question(question).unwrap();
+
+        let qs = Questionnaire::new(job);
+        let form = qs.get_form(0).unwrap();
+
+        assert_eq!(Id::<Section>::new(GENERAL_SECTION_ID), *form.get_section_id());
+        assert_eq!("0, General, orphan, , \n", format!("{}", qs));
+    }
+
+    #[test]
+    fn questionnaire_orphaned_questions_section_zero() {
+        let mut section = Section::new(0, String::from("section 0"), String::new(), HashMap::new());
+        section.add_question(Question::new(Id::<Question>::new(3), String::from("in section"), String::new()));
+
+        let mut job = Job::new(0, String::from("job 1"), String::from("my job"), HashMap::new());
+        job.add_section(section);
+        job.add_orphaned_question(Question::new(Id::<Question>::new(3), String::from("orphan"), String::new())).unwrap();
+
+        let qs = Questionnaire::new(job);
+        let mut titles: Vec<String> = qs.all_forms().iter()
+            .map(|(_, form)| qs.get_section(form.get_section_id()).unwrap().get_title())
+            .collect();
+        titles.sort();
+
+        assert_eq!(vec!["General", "section 0"], titles);
+    }
 
+    #[test]
+    fn questionnaire_starts_unanswered() {
+        let qs = questionnaire_test();
+
+        assert_eq!(QuestionStatus::Unanswered, qs.forms.get(&0).unwrap().get_status());
+        assert!(!qs.is_complete());
+    }
+
+    #[test]
+    fn questionnaire_completeness() {
+        let question = Question::new(Id::<Question>::new(1),String::from("question 2"), String::from("my question"));
+        let mut qs = questionnaire_test();
+        qs.job.add_question(Id::<Section>::new(0), question).unwrap();
+        let mut qs = Questionnaire::new(qs.job);
+
+        qs.update_form_status(0, QuestionStatus::NA).unwrap();
+
+        let completeness = qs.completeness();
+        assert_eq!(Completeness { total: 2, answered: 1, unanswered: 1 }, completeness);
+
+        let unanswered = qs.unanswered_forms();
+        assert_eq!(vec![1], *unanswered.get(&0).unwrap());
+
+        qs.update_form_status(1, QuestionStatus::NO).unwrap();
+        assert!(qs.is_complete());
+        assert_eq!(0, qs.unanswered_forms().len());
+    }
+
+    #[test]
+    fn status_from_display() {
+        for status in [QuestionStatus::OK, QuestionStatus::NA, QuestionStatus::NO, QuestionStatus::Unanswered] {
+            assert_eq!(Ok(status), status.to_string().parse::<QuestionStatus>());
+        }
+    }
+
+    #[test]
+    fn questionnaire_update_form_response() {
+        let scale = ResponseScale::new(1, String::from("Severity"), vec![
+            ResponseOption::new(String::from("P"), String::from("Pass"), QuestionStatus::OK),
+            ResponseOption::new(String::from("MAJ"), String::from("Major"), QuestionStatus::NO),
+        ]);
+        let mut qs = questionnaire_test();
+        qs.job.set_scale(scale);
+        let mut qs = Questionnaire::new(qs.job);
+
+        assert!(qs.update_form_response(0, "OK").is_err());
+
+        qs.update_form_response(0, "MAJ").unwrap();
+        assert_eq!(QuestionStatus::NO, qs.forms.get(&0).unwrap().get_status());
+        assert_eq!("0, section 1, question 1, Major, \n", format!("{}", qs));
+
+        // Only the outcome is known, the first matching option is used.
+        qs.update_form_status(0, QuestionStatus::OK).unwrap();
+        assert_eq!("Pass", qs.forms.get(&0).unwrap().get_label());
+
+        qs.update_form_status(0, QuestionStatus::NA).unwrap();
+        assert_eq!(None, qs.forms.get(&0).unwrap().get_response());
+        assert_eq!("N/A", qs.forms.get(&0).unwrap().get_label());
+    }
+
+    #[test]
+    fn questionnaire_update_form_value() {
+        use crate::data::{Measurement, QuestionKind};
+
+        let mut density = Question::new(Id::<Question>::new(1),String::from("density"), String::from("slurry density"));
+        density.set_kind(QuestionKind::Measurement(Measurement::with_tolerance(String::from("ppg"), 15.8, 0.2)));
+        let mut qs = questionnaire_test();
+        qs.job.add_question(Id::<Section>::new(0), density).unwrap();
+        let mut qs = Questionnaire::new(qs.job);
+
+        let density_id = qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == 1).map(|(id, _)| **id).unwrap();
+        let checklist_id = if density_id == 0 { 1 } else { 0 };
+
+        assert!(qs.update_form_value(checklist_id, 15.8).is_err());
+        assert!(qs.update_form_value(density_id, f64::NAN).is_err());
+
+        qs.update_form_value(density_id, 16.5).unwrap();
+        assert_eq!(QuestionStatus::NO, qs.forms.get(&density_id).unwrap().get_status());
+
+        qs.update_form_value(density_id, 15.9).unwrap();
+        let form = qs.forms.get(&density_id).unwrap();
+        assert_eq!(QuestionStatus::OK, form.get_status());
+        assert_eq!("15.9 ppg", qs.form_value_text(form));
+    }
+
+    #[test]
+    fn questionnaire_verdict() {
+        let mut qs = questionnaire_test();
+        qs.update_form_status(0, QuestionStatus::NO).unwrap();
+
+        assert_eq!(None, qs.verdict());
+
+        qs.job.set_pass_threshold(Some(50.0));
+        assert_eq!(Some(Verdict::Fail), qs.verdict());
+
+        qs.update_form_status(0, QuestionStatus::OK).unwrap();
+        assert_eq!(Some(Verdict::Pass), qs.verdict());
+
+        // Only N/A answers, nothing to judge.
+        qs.update_form_status(0, QuestionStatus::NA).unwrap();
+        assert_eq!(None, qs.verdict());
+    }
+
+    #[test]
+    fn questionnaire_skip_logic() {
+        use crate::rules::{Rule, RuleTarget};
+
+        // Question 1 is a follow-up of question 0, section 1 is hidden when question 0 is N/A.
+        let follow_up = Question::new(Id::<Question>::new(1),String::from("follow up"), String::from(""));
+        let mut squeeze = Section::new(1, String::from("Squeeze"), String::from(""), HashMap::new());
+        squeeze.add_question(Question::new(Id::<Question>::new(2),String::from("squeeze"), String::from("")));
+
+        let mut qs = questionnaire_test();
+        qs.job.add_question(Id::<Section>::new(0), follow_up).unwrap();
+        qs.job.add_section(squeeze);
+        qs.job.add_rule(Rule::new(RuleTarget::Question(1), RuleAction::ShowIf, 0, QuestionStatus::NO));
+        qs.job.add_rule(Rule::new(RuleTarget::Section(1), RuleAction::HideIf, 0, QuestionStatus::NA));
+        // Depends on a question the job does not have.
+        qs.job.add_rule(Rule::new(RuleTarget::Question(2), RuleAction::ShowIf, 9, QuestionStatus::OK));
+        let mut qs = Questionnaire::new(qs.job);
+
+        let id_of = |qs: &Questionnaire, q_id: u64| *qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == q_id).unwrap().0;
+        let (q0, q1, q2) = (id_of(&qs, 0), id_of(&qs, 1), id_of(&qs, 2));
+
+        assert!(!qs.is_form_visible(q1));
+        assert!(qs.is_form_visible(q2));
+        assert_eq!(2, qs.completeness().total);
+
+        qs.update_form_status(q0, QuestionStatus::NO).unwrap();
+        assert!(qs.is_form_visible(q1));
+
+        qs.update_form_status(q0, QuestionStatus::NA).unwrap();
+        assert!(!qs.is_form_visible(q1));
+        assert!(!qs.is_form_visible(q2));
+
+        // Hidden forms do not have to be answered.
+        assert!(qs.is_complete());
+        assert_eq!(1, qs.visible_forms().len());
+    }
+
+    #[test]
+    fn questionnaire_rule_cycle() {
+        use crate::rules::{Rule, RuleTarget};
+
+        let other = Question::new(Id::<Question>::new(1),String::from("other"), String::from(""));
+        let mut qs = questionnaire_test();
+        qs.job.add_question(Id::<Section>::new(0), other).unwrap();
+        qs.job.add_rule(Rule::new(RuleTarget::Question(0), RuleAction::HideIf, 1, QuestionStatus::NO));
+        qs.job.add_rule(Rule::new(RuleTarget::Question(1), RuleAction::HideIf, 0, QuestionStatus::NO));
+        let qs = Questionnaire::new(qs.job);
+
+        assert_eq!(2, qs.visible_forms().len());
+    }
+
+    #[test]
+    fn questionnaire_attachments() {
+        let photo = Attachment::new(String::from("ab12"), String::from("casing.jpg"), String::from("image/jpeg"));
+        let mut qs = questionnaire_test();
+
+        qs.add_form_attachment(0, photo.clone()).unwrap();
+        qs.add_form_attachment(0, photo.clone()).unwrap();
+        assert_eq!(&vec![photo], qs.form_attachments(0).unwrap());
+
+        assert!(qs.remove_form_attachment(0, "cd34").is_err());
+        qs.remove_form_attachment(0, "ab12").unwrap();
+        assert_eq!(0, qs.form_attachments(0).unwrap().len());
+    }
+
+    #[test]
+    fn questionnaire_history() {
+        let mut qs = questionnaire_test();
+        qs.set_engineer(String::from("J. Doe"));
+
+        qs.update_form_status(0, QuestionStatus::NO).unwrap();
+        qs.update_form_status(0, QuestionStatus::NO).unwrap();
+        qs.update_form_notes(0, String::from("Scratched")).unwrap();
+        qs.update_form_status(0, QuestionStatus::OK).unwrap();
+
+        let history = qs.form_history(0);
+        assert_eq!(3, history.len());
+        assert_eq!(FormField::Answer, history[0].field);
+        assert_eq!("", history[0].old_value);
+        assert_eq!("NO", history[0].new_value);
+        assert_eq!("J. Doe", history[0].engineer);
+        assert_eq!("Scratched", history[1].new_value);
+        assert_eq!("NO", history[2].old_value);
+
+        assert_eq!(0, qs.form_history(1).len());
+    }
+
+    #[test]
+    fn approved_questionnaire_is_locked() {
+        let mut qs = questionnaire_test();
+        qs.update_form_status(0, QuestionStatus::OK).unwrap();
+
+        qs.submit().unwrap();
+        qs.approve(String::from("A. Smith")).unwrap();
+
+        assert!(qs.update_form_status(0, QuestionStatus::NO).is_err());
+        assert!(qs.update_form_notes(0, String::from("late note")).is_err());
+        assert_eq!(QuestionStatus::OK, qs.forms[&0].get_status());
+    }
+
+    #[test]
+    fn questionnaire_csv_missing_specification() {
+        let job = questionnaire_test().job;
+        let form = UnitForm::new(0, Id::<Question>::new(9), Id::<Section>::new(0), QuestionStatus::OK, String::new());
+        let qs = Questionnaire::from_forms(job, vec![form]);
+
+        // A form whose specification is gone is an error, not a panic.
+        assert_eq!(Err(QuestionnaireError::MissingQuestion { section_id: 0, question_id: 9 }), qs.to_unit_records().map(|_| ()));
+        assert!(qs.write_csv_to(&mut Vec::new()).is_err());
     }
 
     fn questionnaire_display_status_update() {
@@ -400,7 +1050,7 @@ mod test {
 
         let q_string = format!("{}",qs);
 
-        assert_eq!("0, section 1, question 1, N/A, \n",q_string);
+        assert_eq!("0, section 1, question 1, , \n",q_string);
         qs.update_form_status(0,QuestionStatus::OK);
 
         let q_string = format!("{}",qs);
@@ -0,0 +1,157 @@
+/*
+ * Review and sign-off of a report.
+ *
+ * A report is drafted, submitted for review, then either approved or returned
+ * with comments. A returned report can be corrected and submitted again.
+ * Approved reports can not be edited anymore.
+ */
+use crate::error::QuestionnaireError;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ReviewState {
+    #[default]
+    Draft,
+    Submitted,
+    Approved,
+    Returned,
+}
+
+impl Display for ReviewState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReviewState::Draft => write!(f, "draft"),
+            ReviewState::Submitted => write!(f, "submitted"),
+            ReviewState::Approved => write!(f, "approved"),
+            ReviewState::Returned => write!(f, "returned"),
+        }
+    }
+}
+
+impl FromStr for ReviewState {
+    type Err = QuestionnaireError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft"     => Ok(ReviewState::Draft),
+            "submitted" => Ok(ReviewState::Submitted),
+            "approved"  => Ok(ReviewState::Approved),
+            "returned"  => Ok(ReviewState::Returned),
+            _           => Err(QuestionnaireError::Unknown { kind: "review state", value: s.to_string() })
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Review {
+    state: ReviewState,
+    // Who approved or returned the report.
+    reviewer: Option<String>,
+    // Why the report was returned.
+    comments: String,
+}
+
+impl Review {
+    // Rebuilds a review read back from storage, the transitions are not checked.
+    pub fn new(state: ReviewState, reviewer: Option<String>, comments: String) -> Self {
+        Review { state, reviewer, comments }
+    }
+
+    pub fn get_state(&self) -> ReviewState {
+        self.state
+    }
+
+    pub fn get_reviewer(&self) -> Option<&String> {
+        self.reviewer.as_ref()
+    }
+
+    pub fn get_comments(&self) -> &String {
+        &self.comments
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.state == ReviewState::Approved
+    }
+
+    // The comments of a previous return are kept until the report is reviewed again.
+    pub fn submit(&mut self) -> Result<(), QuestionnaireError> {
+        match self.state {
+            ReviewState::Draft | ReviewState::Returned => {
+                self.state = ReviewState::Submitted;
+                Ok(())
+            },
+            _ => Err(QuestionnaireError::InvalidTransition { from: self.state, to: ReviewState::Submitted })
+        }
+    }
+
+    pub fn approve(&mut self, reviewer: String) -> Result<(), QuestionnaireError> {
+        if self.state != ReviewState::Submitted {
+            return Err(QuestionnaireError::InvalidTransition { from: self.state, to: ReviewState::Approved });
+        }
+
+        if reviewer.trim().is_empty() {
+            return Err(QuestionnaireError::MissingReviewer);
+        }
+
+        self.state = ReviewState::Approved;
+        self.reviewer = Some(reviewer);
+        self.comments = String::new();
+        Ok(())
+    }
+
+    pub fn return_report(&mut self, reviewer: String, comments: String) -> Result<(), QuestionnaireError> {
+        if self.state != ReviewState::Submitted {
+            return Err(QuestionnaireError::InvalidTransition { from: self.state, to: ReviewState::Returned });
+        }
+
+        if reviewer.trim().is_empty() {
+            return Err(QuestionnaireError::MissingReviewer);
+        }
+
+        if comments.trim().is_empty() {
+            return Err(QuestionnaireError::MissingComments);
+        }
+
+        self.state = ReviewState::Returned;
+        self.reviewer = Some(reviewer);
+        self.comments = comments;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_from_display() {
+        for state in [ReviewState::Draft, ReviewState::Submitted, ReviewState::Approved, ReviewState::Returned] {
+            assert_eq!(Ok(state), state.to_string().parse::<ReviewState>());
+        }
+    }
+
+    #[test]
+    fn return_and_approve() {
+        let mut review = Review::default();
+
+        assert_eq!(Err(QuestionnaireError::InvalidTransition { from: ReviewState::Draft, to: ReviewState::Approved }), review.approve(String::from("A. Smith")));
+        review.submit().unwrap();
+        assert!(review.submit().is_err());
+
+        assert_eq!(Err(QuestionnaireError::MissingComments), review.return_report(String::from("A. Smith"), String::new()));
+        review.return_report(String::from("A. Smith"), String::from("Missing photos")).unwrap();
+        assert_eq!(ReviewState::Returned, review.get_state());
+        assert_eq!("Missing photos", review.get_comments());
+        assert!(!review.is_locked());
+
+        review.submit().unwrap();
+        review.approve(String::from("A. Smith")).unwrap();
+        assert_eq!(Some(&String::from("A. Smith")), review.get_reviewer());
+        assert!(review.is_locked());
+        assert!(review.submit().is_err());
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Skip logic between specifications.
+ *
+ * A rule shows or hides a question, or a whole section, depending on the status
+ * of another question. Hidden forms are not asked, scored or reported.
+ */
+use crate::questionnaire::QuestionStatus;
+use crate::error::QuestionnaireError;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum RuleTarget {
+    Question(u64),
+    Section(u64),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum RuleAction {
+    // The target is only asked while the condition holds.
+    ShowIf,
+    // The target is not asked while the condition holds.
+    HideIf,
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::ShowIf => write!(f, "show_if"),
+            RuleAction::HideIf => write!(f, "hide_if"),
+        }
+    }
+}
+
+impl FromStr for RuleAction {
+    type Err = QuestionnaireError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "show_if" => Ok(RuleAction::ShowIf),
+            "hide_if" => Ok(RuleAction::HideIf),
+            _         => Err(QuestionnaireError::Unknown { kind: "rule action", value: s.to_string() })
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub target: RuleTarget,
+    pub action: RuleAction,
+    // Question whose status is checked.
+    pub depends_on: u64,
+    pub status: QuestionStatus,
+}
+
+impl Rule {
+    pub fn new(target: RuleTarget, action: RuleAction, depends_on: u64, status: QuestionStatus) -> Self {
+        Rule { target, action, depends_on, status }
+    }
+
+    pub fn applies_to(&self, section_id: u64, question_id: u64) -> bool {
+        match self.target {
+            RuleTarget::Question(id) => id == question_id,
+            RuleTarget::Section(id) => id == section_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_from_display() {
+        for action in [RuleAction::ShowIf, RuleAction::HideIf] {
+            assert_eq!(Ok(action), action.to_string().parse::<RuleAction>());
+        }
+    }
+
+    #[test]
+    fn applies_to() {
+        let rule = Rule::new(RuleTarget::Section(2), RuleAction::HideIf, 3, QuestionStatus::NA);
+
+        assert!(rule.applies_to(2, 7));
+        assert!(!rule.applies_to(1, 2));
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Response scales define which answers a specification accepts.
+ *
+ * Each option of a scale maps to a *QuestionStatus*, which is what compliance
+ * is computed from. The option itself is what gets shown in reports.
+ */
+use crate::questionnaire::QuestionStatus;
+
+use serde::{Serialize, Deserialize};
+
+// Id of the built-in OK / N/A / NO scale. Database ids start at 1.
+pub const DEFAULT_SCALE_ID: u64 = 0;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ResponseOption {
+    pub code: String,
+    pub label: String,
+    // Outcome of choosing this option.
+    pub status: QuestionStatus,
+}
+
+impl ResponseOption {
+    pub fn new(code: String, label: String, status: QuestionStatus) -> Self {
+        ResponseOption { code, label, status }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ResponseScale {
+    pub id: u64,
+    pub name: String,
+    // Ordered as they should be presented.
+    options: Vec<ResponseOption>,
+}
+
+impl Default for ResponseScale {
+    fn default() -> Self {
+        ResponseScale::new(DEFAULT_SCALE_ID, String::from("OK / N/A / NO"), vec![
+            ResponseOption::new(String::from("OK"), String::from("OK"), QuestionStatus::OK),
+            ResponseOption::new(String::from("N/A"), String::from("N/A"), QuestionStatus::NA),
+            ResponseOption::new(String::from("NO"), String::from("NO"), QuestionStatus::NO),
+        ])
+    }
+}
+
+impl ResponseScale {
+    pub fn new(id: u64, name: String, options: Vec<ResponseOption>) -> Self {
+        ResponseScale { id, name, options }
+    }
+
+    pub fn options(&self) -> &Vec<ResponseOption> {
+        &self.options
+    }
+
+    pub fn get_option(&self, code: &str) -> Option<&ResponseOption> {
+        self.options.iter().find(|option| option.code == code)
+    }
+
+    // First option with the given outcome, used when only a status is known.
+    pub fn option_for_status(&self, status: QuestionStatus) -> Option<&ResponseOption> {
+        self.options.iter().find(|option| option.status == status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn severity_scale() -> ResponseScale {
+        ResponseScale::new(1, String::from("Severity"), vec![
+            ResponseOption::new(String::from("P"), String::from("Pass"), QuestionStatus::OK),
+            ResponseOption::new(String::from("MIN"), String::from("Minor"), QuestionStatus::OK),
+            ResponseOption::new(String::from("MAJ"), String::from("Major"), QuestionStatus::NO),
+            ResponseOption::new(String::from("CRIT"), String::from("Critical"), QuestionStatus::NO),
+        ])
+    }
+
+    #[test]
+    fn get_option() {
+        let scale = severity_scale();
+
+        assert_eq!("Major", scale.get_option("MAJ").unwrap().label);
+        assert_eq!(None, scale.get_option("OK"));
+    }
+
+    #[test]
+    fn option_for_status() {
+        let scale = severity_scale();
+
+        assert_eq!("P", scale.option_for_status(QuestionStatus::OK).unwrap().code);
+        assert_eq!(None, scale.option_for_status(QuestionStatus::NA));
+        assert_eq!("N/A", ResponseScale::default().option_for_status(QuestionStatus::NA).unwrap().code);
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * Weighted compliance of a questionnaire.
+ *
+ * A specification answered OK earns its weight, one answered NO only adds to
+ * what could have been earned. N/A and unanswered specifications are not scored.
+ */
+use crate::questionnaire::{Questionnaire, QuestionStatus};
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Score {
+    pub earned: u64,
+    pub possible: u64,
+}
+
+impl Score {
+    fn add(&mut self, status: QuestionStatus, weight: u32) {
+        match status {
+            QuestionStatus::OK => {
+                self.earned += weight as u64;
+                self.possible += weight as u64;
+            },
+            QuestionStatus::NO => self.possible += weight as u64,
+            QuestionStatus::NA | QuestionStatus::Unanswered => {}
+        }
+    }
+
+    // None when there is nothing to score, e.g. only N/A answers or weights of 0.
+    pub fn percentage(&self) -> Option<f64> {
+        if self.possible == 0 {
+            None
+        } else {
+            Some(self.earned as f64 / self.possible as f64 * 100.0)
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.percentage() {
+            Some(percentage) => write!(f, "{:.1}%", percentage),
+            None => write!(f, "not scored"),
+        }
+    }
+}
+
+// Number of forms with each status, regardless of weight.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct StatusCounts {
+    pub ok: usize,
+    pub no: usize,
+    pub na: usize,
+    pub unanswered: usize,
+}
+
+impl StatusCounts {
+    fn add(&mut self, status: QuestionStatus) {
+        match status {
+            QuestionStatus::OK => self.ok += 1,
+            QuestionStatus::NO => self.no += 1,
+            QuestionStatus::NA => self.na += 1,
+            QuestionStatus::Unanswered => self.unanswered += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.ok + self.no + self.na + self.unanswered
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SectionScore {
+    pub section_id: u64,
+    pub title: String,
+    pub score: Score,
+    pub counts: StatusCounts,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportScore {
+    // Ordered by section id.
+    pub sections: Vec<SectionScore>,
+    pub overall: Score,
+    pub counts: StatusCounts,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Verdict {
+    Pass,
+    Fail,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "PASS"),
+            Verdict::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+impl ReportScore {
+    // *pass_threshold* is the minimum overall percentage. A report with nothing to score is not judged.
+    pub fn verdict(&self, pass_threshold: f64) -> Option<Verdict> {
+        self.overall.percentage().map(|percentage| {
+            if percentage >= pass_threshold {
+                Verdict::Pass
+            } else {
+                Verdict::Fail
+            }
+        })
+    }
+}
+
+pub fn score(questionnaire: &Questionnaire) -> ReportScore {
+    let mut sections: HashMap<u64, SectionScore> = HashMap::new();
+    let mut overall = Score::default();
+    let mut counts = StatusCounts::default();
+
+    // Hidden forms are not asked, so they do not count.
+    for (_, form) in questionnaire.visible_forms() {
+        let weight = match questionnaire.get_question(form.get_section_id(), form.get_question_id()) {
+            Ok(question) => question.get_weight(),
+            Err(_) => continue,
+        };
+
+        let section_id = form.get_section_id().primitive();
+        let section_score = sections.entry(section_id).or_insert_with(|| {
+            let title = match questionnaire.get_section(form.get_section_id()) {
+                Ok(section) => section.get_title(),
+                Err(_) => String::new(),
+            };
+
+            SectionScore { section_id, title, score: Score::default(), counts: StatusCounts::default() }
+        });
+
+        section_score.score.add(form.get_status(), weight);
+        section_score.counts.add(form.get_status());
+        overall.add(form.get_status(), weight);
+        counts.add(form.get_status());
+    }
+
+    let mut sections: Vec<SectionScore> = sections.into_values().collect();
+    sections.sort_by_key(|s| s.section_id);
+
+    ReportScore { sections, overall, counts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Id, Question, Section};
+    use crate::job::Job;
+
+    // Section 0 has questions 0 (weight 1) and 1 (weight 3), section 1 has question 2 (weight 1).
+    fn questionnaire_test() -> Questionnaire {
+        let mut heavy = Question::new(Id::<Question>::new(1), String::from("question 2"), String::from(""));
+        heavy.set_weight(3);
+
+        let mut s1 = Section::new(0, String::from("section 1"), String::from(""), HashMap::new());
+        s1.add_question(Question::new(Id::<Question>::new(0), String::from("question 1"), String::from("")));
+        s1.add_question(heavy);
+        let mut s2 = Section::new(1, String::from("section 2"), String::from(""), HashMap::new());
+        s2.add_question(Question::new(Id::<Question>::new(2), String::from("question 3"), String::from("")));
+
+        let mut job = Job::new(0, String::from("job 1"), String::from(""), HashMap::new());
+        job.add_section(s1);
+        job.add_section(s2);
+
+        Questionnaire::new(job)
+    }
+
+    fn form_id(qs: &Questionnaire, question_id: u64) -> u64 {
+        *qs.all_forms().iter().find(|(_, form)| form.get_question_id().primitive() == question_id).unwrap().0
+    }
+
+    #[test]
+    fn weighted_score() {
+        let mut qs = questionnaire_test();
+        qs.update_form_status(form_id(&qs, 0), QuestionStatus::NO).unwrap();
+        qs.update_form_status(form_id(&qs, 1), QuestionStatus::OK).unwrap();
+        qs.update_form_status(form_id(&qs, 2), QuestionStatus::NA).unwrap();
+
+        let report = score(&qs);
+
+        assert_eq!(Score { earned: 3, possible: 4 }, report.overall);
+        assert_eq!(Some(75.0), report.overall.percentage());
+
+        assert_eq!(2, report.sections.len());
+        assert_eq!("section 1", report.sections[0].title);
+        assert_eq!(Score { earned: 3, possible: 4 }, report.sections[0].score);
+        // Only N/A answers.
+        assert_eq!(None, report.sections[1].score.percentage());
+        assert_eq!(StatusCounts { ok: 1, no: 1, na: 1, unanswered: 0 }, report.counts);
+        assert_eq!(1, report.sections[1].counts.na);
+    }
+
+    #[test]
+    fn verdict() {
+        let mut qs = questionnaire_test();
+        qs.update_form_status(form_id(&qs, 0), QuestionStatus::OK).unwrap();
+        qs.update_form_status(form_id(&qs, 1), QuestionStatus::NO).unwrap();
+        qs.update_form_status(form_id(&qs, 2), QuestionStatus::OK).unwrap();
+
+        let report = score(&qs);
+
+        assert_eq!(Some(40.0), report.overall.percentage());
+        assert_eq!(Some(Verdict::Pass), report.verdict(40.0));
+        assert_eq!(Some(Verdict::Fail), report.verdict(80.0));
+    }
+
+    #[test]
+    fn all_na_not_scored() {
+        let mut qs = questionnaire_test();
+        for question_id in 0..3 {
+            qs.update_form_status(form_id(&qs, question_id), QuestionStatus::NA).unwrap();
+        }
+
+        let report = score(&qs);
+
+        assert_eq!(None, report.overall.percentage());
+        assert_eq!("not scored", report.overall.to_string());
+        assert_eq!(None, report.verdict(0.0));
+    }
+}
@@ -1,5 +1,9 @@
 use rusqlite::{Connection, Result};
 
+use crate::migrations;
+
+use questionnaire::document::ReportHeader;
+
 #[derive(Debug, PartialEq)]
 pub struct JobType {
     pub id: u64,
@@ -39,11 +43,6 @@ impl Specification {
     }
 }
 
-struct JobSpecification {
-    job_type_id: u64,
-    specification_id: u64,
-}
-
 // Maps the ids to their respective names/content. 
 #[derive(Debug)]
 pub struct JobSpecificationSection {
@@ -88,6 +87,215 @@ impl JobSpecificationSection {
     }
 }
 
+// Answer given to a single specification of a report.
+// The section name and specification content are a copy of the template at the time
+// the report was stored, so later edits of the template do not change the report.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportUnitForm {
+    pub form_id: u64,
+    pub section_id: u64,
+    pub section_name: String,
+    pub specification_id: u64,
+    pub specification_content: String,
+    pub status: String,
+    pub notes: String,
+    // Option of the response scale that was chosen, if any.
+    pub response_code: Option<String>,
+    pub response_label: Option<String>,
+    // Limits the value was checked against, for measurements.
+    pub measurement: Option<Measurement>,
+    pub value: Option<f64>,
+    pub weight: u32,
+    pub attachments: Vec<ReportAttachment>,
+    // Scale the form was answered with, none for reports stored before scales were kept.
+    pub scale: Option<ResponseScale>,
+}
+
+impl ReportUnitForm {
+    pub fn new(form_id: u64, section_id: u64, section_name: String, specification_id: u64, specification_content: String, status: String, notes: String) -> Self {
+        Self { form_id, section_id, section_name, specification_id, specification_content, status, notes, response_code: None, response_label: None, measurement: None, value: None, weight: 1, attachments: Vec::new(), scale: None }
+    }
+
+    pub fn with_response(mut self, code: String, label: String) -> Self {
+        self.response_code = Some(code);
+        self.response_label = Some(label);
+        self
+    }
+
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_attachments(mut self, attachments: Vec<ReportAttachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    pub fn with_measurement(mut self, measurement: Measurement, value: Option<f64>) -> Self {
+        self.measurement = Some(measurement);
+        self.value = value;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: ResponseScale) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+}
+
+// Reference to a file in the attachment directory.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportAttachment {
+    pub hash: String,
+    pub file_name: String,
+    pub media_type: String,
+}
+
+impl ReportAttachment {
+    pub fn new(hash: String, file_name: String, media_type: String) -> Self {
+        Self { hash, file_name, media_type }
+    }
+}
+
+// Specification answered with a value. A missing limit is not checked.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Measurement {
+    pub unit: String,
+    pub nominal_value: f64,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+impl Measurement {
+    pub fn new(unit: String, nominal_value: f64, min_value: Option<f64>, max_value: Option<f64>) -> Self {
+        Self { unit, nominal_value, min_value, max_value }
+    }
+}
+
+// Used when listing reports, without loading every unit form.
+#[derive(Debug, PartialEq)]
+pub struct ReportSummary {
+    pub id: u64,
+    pub job_type_id: Option<u64>,
+    pub job_name: String,
+    // Revision of the job template the report was answered against.
+    pub template_revision: Option<u64>,
+    pub header: ReportHeader,
+    pub score: Option<ReportScore>,
+    pub review: ReportReview,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub id: u64,
+    pub job_type_id: Option<u64>,
+    pub job_name: String,
+    pub template_revision: Option<u64>,
+    pub header: ReportHeader,
+    pub score: Option<ReportScore>,
+    pub review: ReportReview,
+    // Every form, including the ones hidden by the rules when the report was stored.
+    pub forms: Vec<ReportUnitForm>,
+    // Copy of the rules of the job, the ids are the ones of the copy.
+    pub rules: Vec<SpecificationRule>,
+}
+
+// Weighted compliance of a report, in percent, and whether it passed the threshold of its job type.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportScore {
+    pub score: f64,
+    pub pass_threshold: Option<f64>,
+    // PASS or FAIL, none without a threshold.
+    pub verdict: Option<String>,
+}
+
+impl ReportScore {
+    pub fn new(score: f64, pass_threshold: Option<f64>, verdict: Option<String>) -> Self {
+        Self { score, pass_threshold, verdict }
+    }
+}
+
+// One edit of a form. *field* and the values are stored as shown in reports.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportFormChange {
+    pub form_id: u64,
+    // Seconds since the Unix epoch.
+    pub timestamp: i64,
+    pub engineer: String,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl ReportFormChange {
+    pub fn new(form_id: u64, timestamp: i64, engineer: String, field: String, old_value: String, new_value: String) -> Self {
+        Self { form_id, timestamp, engineer, field, old_value, new_value }
+    }
+}
+
+// Everything stored for a finished report, besides the job type it was answered against.
+pub struct ReportRecord<'a> {
+    pub header: &'a ReportHeader,
+    pub forms: &'a [ReportUnitForm],
+    pub rules: &'a [SpecificationRule],
+    // None for reports with nothing to score.
+    pub score: Option<&'a ReportScore>,
+    pub history: &'a [ReportFormChange],
+    pub review: &'a ReportReview,
+}
+
+// Where the report is in its review: draft, submitted, approved or returned.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReportReview {
+    pub state: String,
+    pub reviewer: Option<String>,
+    // Why the report was returned.
+    pub comments: String,
+}
+
+impl ReportReview {
+    pub fn new(state: String, reviewer: Option<String>, comments: String) -> Self {
+        Self { state, reviewer, comments }
+    }
+}
+
+impl Default for ReportReview {
+    fn default() -> Self {
+        Self::new("draft".to_string(), None, String::new())
+    }
+}
+
+// Answer a specification accepts. *status* is the outcome the answer counts as (OK, NO, N/A).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResponseOption {
+    pub id: u64,
+    pub code: String,
+    pub label: String,
+    pub status: String,
+    pub position: u64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResponseScale {
+    pub id: u64,
+    pub scale_name: String,
+    // Ordered by position.
+    pub options: Vec<ResponseOption>,
+}
+
+// Shows or hides a specification, or a whole section, depending on the status of another specification.
+// Exactly one of the targets is set. *action* is either show_if or hide_if.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpecificationRule {
+    pub id: u64,
+    pub target_specification_id: Option<u64>,
+    pub target_section_id: Option<u64>,
+    pub depends_on_specification_id: u64,
+    pub status: String,
+    pub action: String,
+}
+
 pub struct DBQualityControl;
 
 /*
@@ -117,6 +325,25 @@ macro_rules! table_manipulation {
 /* The handle struct manages closing errors */
 impl DBQualityControl {
 
+    // Creates the schema of a new database, or upgrades an existing one to the latest version.
+    pub fn initialize(conn: &Connection) -> Result<u32> {
+        DBQualityControl::migrate(conn, migrations::LATEST_VERSION)
+    }
+
+    // Upgrades the schema up to *target_version*. Returns the version the database is at.
+    pub fn migrate(conn: &Connection, target_version: u32) -> Result<u32> {
+        migrations::migrate(conn, target_version)
+    }
+
+    pub fn schema_version(conn: &Connection) -> Result<u32> {
+        migrations::schema_version(conn)
+    }
+
+    // Removes every table. Used to rebuild a database from scratch.
+    pub fn drop_schema(conn: &Connection) -> Result<()> {
+        migrations::drop_schema(conn)
+    }
+
     pub fn job_has_specification(conn: &Connection, job_type_id: u64, specification_id: u64) -> Result<bool> {
 
         match conn.query_row("SELECT job_type_id specification_id FROM job_specification WHERE job_type_id = ?1 AND specification_id = ?2",[job_type_id,specification_id],
@@ -155,8 +382,7 @@ impl DBQualityControl {
         let op_section_id = {
 
             let mut stmt = conn.prepare("SELECT id FROM section WHERE section_name = ?")?;
-            let section_id = stmt.query_row([additive_section_name], |row| row.get::<usize,u64>(0));
-            section_id
+            stmt.query_row([additive_section_name], |row| row.get::<usize,u64>(0))
 
         };
 
@@ -169,6 +395,34 @@ impl DBQualityControl {
 
     }
 
+    pub fn remove_additive_section(conn: &Connection, section_id: u64) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("DELETE FROM additive_section WHERE section_id = ?1")?;
+            stmt.execute([section_id])
+        }
+    }
+
+    pub fn get_all_additive_sections(conn: &Connection) -> Result<Vec<Section>> {
+        {
+            let mut stmt = conn.prepare("SELECT section.id, section_name FROM additive_section 
+                                         INNER JOIN section ON section_id = section.id
+                                         ORDER BY section.id")?;
+            let rows = stmt.query_map([], |row| Ok(
+                                                    Section {
+                                                        id: row.get(0)?,
+                                                        section_name: row.get(1)?
+                                                    }
+                                                ))?;
+
+            let mut sections = Vec::new();
+            for row in rows {
+                sections.push(row?);
+            }
+
+            Ok(sections)
+        }
+    }
+
     pub fn add_job_type(conn: &Connection,job_type_name: String) -> Result<usize> {
 
         {
@@ -203,15 +457,20 @@ impl DBQualityControl {
         conn.execute("PRAGMA foreign_keys = 1", [])?;
         {
             let mut stmt = conn.prepare("INSERT INTO job_specification (job_type_id, specification_id) VALUES (?1, ?2)")?;
-            stmt.execute((job_type_id, specification_id))
+            let inserted = stmt.execute((job_type_id, specification_id))?;
 
+            DBQualityControl::bump_job_revision(conn, job_type_id)?;
+            Ok(inserted)
         }
     }
 
     pub fn update_section(conn: &Connection, section_id: u64, section_name: String) -> Result<usize> {
         {
             let mut stmt = conn.prepare("UPDATE section SET section_name = ?2 WHERE id = ?1")?;
-            stmt.execute((section_id, section_name))
+            let updated = stmt.execute((section_id, section_name))?;
+
+            DBQualityControl::bump_section_revision(conn, section_id)?;
+            Ok(updated)
 
         }
     }
@@ -226,19 +485,41 @@ impl DBQualityControl {
     pub fn update_specification_content(conn: &Connection, specification_id: u64, specification_name: String) -> Result<usize> {
         {
             let mut stmt = conn.prepare("UPDATE specification SET specification_content = ?2 WHERE id = ?1")?;
-            stmt.execute((specification_id, specification_name))
+            let updated = stmt.execute((specification_id, specification_name))?;
+
+            DBQualityControl::bump_specification_revision(conn, specification_id)?;
+            Ok(updated)
         }
     }
 
     pub fn update_specification_section(conn: &Connection, specification_id: u64, section_id: u64) -> Result<usize> {
         {
             let mut stmt = conn.prepare("UPDATE specification SET section_id = ?2 WHERE id = ?1")?;
-            stmt.execute((specification_id, section_id))
+            let updated = stmt.execute((specification_id, section_id))?;
+
+            DBQualityControl::bump_specification_revision(conn, specification_id)?;
+            Ok(updated)
+        }
+    }
+
+    // Leaves the specification without a section.
+    pub fn remove_specification_section(conn: &Connection, specification_id: u64) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("UPDATE specification SET section_id = NULL WHERE id = ?1")?;
+            let updated = stmt.execute([specification_id])?;
+
+            DBQualityControl::bump_specification_revision(conn, specification_id)?;
+            Ok(updated)
         }
     }
 
     pub fn remove_specification(conn: &Connection, specification_id: u64) -> Result<usize> {
+        // Rules, measurements and job specifications are removed through the cascade.
+        conn.execute("PRAGMA foreign_keys = 1", [])?;
         {
+            // Bump before deleting, the job specifications are removed alongside it.
+            DBQualityControl::bump_specification_revision(conn, specification_id)?;
+
             let mut stmt = conn.prepare("DELETE FROM specification WHERE id = ?1")?;
             stmt.execute([specification_id])
         }
@@ -246,6 +527,8 @@ impl DBQualityControl {
 
     pub fn remove_section(conn: &Connection, section_id: u64) -> Result<usize> {
         {
+            DBQualityControl::bump_section_revision(conn, section_id)?;
+
             let mut stmt = conn.prepare("DELETE FROM section WHERE id = ?1")?;
             stmt.execute([section_id])
         }
@@ -261,7 +544,10 @@ impl DBQualityControl {
     pub fn remove_job_spec(conn: &Connection, job_type_id: u64, specification_id: u64) -> Result<usize> {
         {
             let mut stmt = conn.prepare("DELETE FROM job_specification WHERE job_type_id = ?1 AND specification_id = ?2")?;
-            stmt.execute([job_type_id,specification_id])
+            let removed = stmt.execute([job_type_id,specification_id])?;
+
+            DBQualityControl::bump_job_revision(conn, job_type_id)?;
+            Ok(removed)
         }
     }
 
@@ -332,7 +618,8 @@ impl DBQualityControl {
     }
 
     pub fn get_specification(conn: &Connection, specification_id: u64) -> Result<Specification> {
-        conn.query_row("SELECT specification.id, specification_content, section_id, section_name FROM specification INNER JOIN section ON section.id = section_id WHERE specification.id = ?1",
+        // Specifications without a section are returned as well.
+        conn.query_row("SELECT specification.id, specification_content, section.id, section_name FROM specification LEFT JOIN section ON section.id = section_id WHERE specification.id = ?1",
                        [specification_id],
                        |row| {
                            // Build section
@@ -360,10 +647,10 @@ impl DBQualityControl {
 
     pub fn get_all_specifications(conn: &Connection) -> Result<Vec<Specification>> {
         {
-            let mut stmt = conn.prepare("SELECT specification.id, specification_content, section.id, section_name FROM specification INNER JOIN section ON section.id = section_id")?;
+            let mut stmt = conn.prepare("SELECT specification.id, specification_content, section.id, section_name FROM specification LEFT JOIN section ON section.id = section_id")?;
 
             let rows = stmt.query_map([],|row| {
-                let section = DBQualityControl::row_section(&row)?;
+                let section = DBQualityControl::row_section(row)?;
 
                 Ok(
                     Specification {
@@ -384,6 +671,35 @@ impl DBQualityControl {
         }
     }
 
+    // All specifications belonging to a section, used to bolt an additive section onto a job.
+    pub fn get_section_specifications(conn: &Connection, section_id: u64) -> Result<Vec<Specification>> {
+        {
+            let mut stmt = conn.prepare("SELECT specification.id, specification_content, section.id, section_name 
+                                         FROM specification 
+                                         INNER JOIN section ON section.id = section_id 
+                                         WHERE section_id = ?1
+                                         ORDER BY specification.id")?;
+
+            let rows = stmt.query_map([section_id],|row| {
+                let section = DBQualityControl::row_section(row)?;
+
+                Ok(
+                    Specification {
+                        id: row.get(0)?,
+                        specification_content: row.get(1)?,
+                        section
+                    }
+                    )
+            })?;
+
+            let mut specs = Vec::new();
+            for row in rows {
+                specs.push(row?);
+            }
+            Ok(specs)
+        }
+    }
+
     fn row_section(row: &rusqlite::Row) -> Result<Option<Section>> {
            let section = match (row.get_ref(2)?.as_i64_or_null()?, row.get_ref(3)?.as_str_or_null()?) {
                (Some(s_id),Some(s_name)) => {
@@ -399,11 +715,11 @@ impl DBQualityControl {
 
     pub fn get_all_job_specification(conn: &Connection, job_type_id: u64) -> Result<Vec<JobSpecificationSection>> {
         {
-            let mut stmt = conn.prepare("SELECT job_type_id, job_type_name, section_id, section_name, specification_id, specification_content 
+            let mut stmt = conn.prepare("SELECT job_type_id, job_type_name, section.id, section_name, specification_id, specification_content 
                                          FROM job_specification
                                          INNER JOIN specification ON specification_id = specification.id
                                          INNER JOIN job_type ON job_type_id = job_type.id
-                                         INNER JOIN section ON section_id = section.id
+                                         LEFT JOIN section ON section_id = section.id
                                          WHERE job_type_id = ?1
                                          ")?;
 
@@ -440,6 +756,601 @@ impl DBQualityControl {
         }
     }
 
-}
+    // Stores a finished report with its rules, score, history and review. Either all of it is stored, or nothing is.
+    pub fn save_report(conn: &Connection, job_type_id: Option<u64>, record: &ReportRecord) -> Result<u64> {
+        let tx = conn.unchecked_transaction()?;
+
+        let report_id = DBQualityControl::insert_report(&tx, job_type_id, record.header, record.forms)?;
+        DBQualityControl::insert_report_record(&tx, report_id, record)?;
+
+        tx.commit()?;
+
+        Ok(report_id)
+    }
+
+    // Replaces everything stored for the report *report_id*, e.g. once a returned report is corrected.
+    // The job type and template revision it was answered against are kept.
+    pub fn update_report(conn: &Connection, report_id: u64, record: &ReportRecord) -> Result<usize> {
+        let tx = conn.unchecked_transaction()?;
+
+        let count = tx.execute("UPDATE report SET score = NULL, pass_threshold = NULL, verdict = NULL WHERE id = ?1", [report_id])?;
+        if count == 0 {
+            return Ok(0);
+        }
+
+        for table in ["report_header", "report_unit_form", "report_form_option", "report_attachment", "report_rule", "report_form_change"] {
+            tx.execute(&format!("DELETE FROM {} WHERE report_id = ?1", table), [report_id])?;
+        }
+
+        DBQualityControl::insert_report_content(&tx, report_id, record.header, record.forms)?;
+        DBQualityControl::insert_report_record(&tx, report_id, record)?;
+
+        tx.commit()?;
+
+        Ok(count)
+    }
+
+    // Rules, score, history and review of the report, run within the transaction of the caller.
+    fn insert_report_record(tx: &Connection, report_id: u64, record: &ReportRecord) -> Result<()> {
+        DBQualityControl::insert_report_rules(tx, report_id, record.rules)?;
+        if let Some(score) = record.score {
+            DBQualityControl::set_report_score(tx, report_id, score)?;
+        }
+        DBQualityControl::insert_report_history(tx, report_id, record.history)?;
+        DBQualityControl::set_report_review(tx, report_id, record.review)?;
+
+        Ok(())
+    }
+
+    // Run within the transaction of the caller.
+    fn insert_report(tx: &Connection, job_type_id: Option<u64>, header: &ReportHeader, forms: &[ReportUnitForm]) -> Result<u64> {
+        // Keep the name and revision of the template the report was answered against.
+        match job_type_id {
+            Some(id) => tx.execute("INSERT INTO report (job_type_id, job_name, template_revision)
+                                    SELECT id, job_type_name, template_revision FROM job_type WHERE id = ?1", [id])?,
+            None     => tx.execute("INSERT INTO report (job_type_id, job_name, template_revision) VALUES (NULL, '', NULL)", [])?,
+        };
+
+        if tx.changes() == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+
+        let report_id = u64::try_from(tx.last_insert_rowid()).unwrap();
+        DBQualityControl::insert_report_content(tx, report_id, header, forms)?;
+
+        Ok(report_id)
+    }
+
+    // Header and forms of the report, run within the transaction of the caller.
+    fn insert_report_content(tx: &Connection, report_id: u64, header: &ReportHeader, forms: &[ReportUnitForm]) -> Result<()> {
+        tx.execute("INSERT INTO report_header (report_id, engineer, job, day, month, year) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                   (report_id, &header.engineer, &header.job, header.day, header.month, header.year))?;
+
+        {
+            let mut stmt = tx.prepare("INSERT INTO report_unit_form (report_id, form_id, section_id, section_name, specification_id, specification_content, status, notes, response_code, response_label,
+                                                                     value, measurement_unit, nominal_value, min_value, max_value, weight, scale_id, scale_name) 
+                                       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)")?;
+
+            for form in forms {
+                let measurement = form.measurement.as_ref();
+                let scale = form.scale.as_ref();
+
+                stmt.execute(rusqlite::params![report_id, form.form_id, form.section_id, &form.section_name, form.specification_id, &form.specification_content, &form.status, &form.notes, &form.response_code, &form.response_label,
+                                               form.value, measurement.map(|m| &m.unit), measurement.map(|m| m.nominal_value), measurement.and_then(|m| m.min_value), measurement.and_then(|m| m.max_value), form.weight,
+                                               scale.map(|s| s.id), scale.map(|s| &s.scale_name)])?;
+            }
+
+            let mut stmt = tx.prepare("INSERT INTO report_form_option (report_id, form_id, code, label, status, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+
+            for form in forms {
+                for option in form.scale.iter().flat_map(|scale| scale.options.iter()) {
+                    stmt.execute((report_id, form.form_id, &option.code, &option.label, &option.status, option.position))?;
+                }
+            }
+
+            let mut stmt = tx.prepare("INSERT INTO report_attachment (report_id, form_id, hash, file_name, media_type) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+
+            for form in forms {
+                for attachment in form.attachments.iter() {
+                    stmt.execute((report_id, form.form_id, &attachment.hash, &attachment.file_name, &attachment.media_type))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
 
+    // Run within the transaction of the caller. The ids of *rules* are ignored.
+    fn insert_report_rules(tx: &Connection, report_id: u64, rules: &[SpecificationRule]) -> Result<usize> {
+        let mut stmt = tx.prepare("INSERT INTO report_rule (report_id, target_specification_id, target_section_id, depends_on_specification_id, status, action)
+                                   VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
 
+        for rule in rules {
+            stmt.execute((report_id, rule.target_specification_id, rule.target_section_id, rule.depends_on_specification_id, &rule.status, &rule.action))?;
+        }
+
+        Ok(rules.len())
+    }
+
+    pub fn get_report(conn: &Connection, report_id: u64) -> Result<Report> {
+        let summary = conn.query_row("SELECT report.id, job_type_id, job_name, template_revision, engineer, job, day, month, year, score, pass_threshold, verdict,
+                                             review_state, reviewer, review_comments
+                                      FROM report
+                                      INNER JOIN report_header ON report_id = report.id
+                                      WHERE report.id = ?1",
+                                      [report_id],
+                                      DBQualityControl::row_report_summary)?;
+
+        let forms = {
+            let mut stmt = conn.prepare("SELECT form_id, section_id, section_name, specification_id, specification_content, status, notes, response_code, response_label,
+                                                value, measurement_unit, nominal_value, min_value, max_value, weight, scale_id, scale_name 
+                                         FROM report_unit_form WHERE report_id = ?1 ORDER BY form_id")?;
+            let rows = stmt.query_map([report_id], |row| {
+                                            let measurement = match (row.get::<_, Option<String>>(10)?, row.get::<_, Option<f64>>(11)?) {
+                                                (Some(unit), Some(nominal_value)) => Some(Measurement::new(unit, nominal_value, row.get(12)?, row.get(13)?)),
+                                                _ => None
+                                            };
+
+                                            Ok(
+                                                ReportUnitForm {
+                                                    form_id: row.get(0)?,
+                                                    section_id: row.get(1)?,
+                                                    section_name: row.get(2)?,
+                                                    specification_id: row.get(3)?,
+                                                    specification_content: row.get(4)?,
+                                                    status: row.get(5)?,
+                                                    notes: row.get(6)?,
+                                                    response_code: row.get(7)?,
+                                                    response_label: row.get(8)?,
+                                                    measurement,
+                                                    value: row.get(9)?,
+                                                    weight: row.get(14)?,
+                                                    attachments: Vec::new(),
+                                                    scale: match row.get::<_, Option<u64>>(15)? {
+                                                        Some(scale_id) => Some(ResponseScale { id: scale_id, scale_name: row.get(16)?, options: Vec::new() }),
+                                                        None => None
+                                                    },
+                                                }
+                                            )})?;
+
+            let mut forms = Vec::new();
+            for row in rows {
+                forms.push(row?);
+            }
+            forms
+        };
+
+        let mut forms = forms;
+        {
+            let mut stmt = conn.prepare("SELECT form_id, hash, file_name, media_type FROM report_attachment WHERE report_id = ?1 ORDER BY form_id, rowid")?;
+            let rows = stmt.query_map([report_id], |row| Ok((row.get::<_, u64>(0)?, ReportAttachment::new(row.get(1)?, row.get(2)?, row.get(3)?))))?;
+
+            for row in rows {
+                let (form_id, attachment) = row?;
+                if let Some(form) = forms.iter_mut().find(|form| form.form_id == form_id) {
+                    form.attachments.push(attachment);
+                }
+            }
+
+            let mut stmt = conn.prepare("SELECT form_id, id, code, label, status, position FROM report_form_option WHERE report_id = ?1 ORDER BY form_id, position")?;
+            let rows = stmt.query_map([report_id], |row| {
+                Ok((row.get::<_, u64>(0)?, ResponseOption { id: row.get(1)?, code: row.get(2)?, label: row.get(3)?, status: row.get(4)?, position: row.get(5)? }))
+            })?;
+
+            for row in rows {
+                let (form_id, option) = row?;
+                if let Some(scale) = forms.iter_mut().find(|form| form.form_id == form_id).and_then(|form| form.scale.as_mut()) {
+                    scale.options.push(option);
+                }
+            }
+        }
+
+        let rules = {
+            let mut stmt = conn.prepare("SELECT id, target_specification_id, target_section_id, depends_on_specification_id, status, action
+                                         FROM report_rule WHERE report_id = ?1 ORDER BY id")?;
+            let rows = stmt.query_map([report_id], DBQualityControl::row_specification_rule)?;
+
+            let mut rules = Vec::new();
+            for row in rows {
+                rules.push(row?);
+            }
+            rules
+        };
+
+        Ok(
+            Report {
+                id: summary.id,
+                job_type_id: summary.job_type_id,
+                job_name: summary.job_name,
+                template_revision: summary.template_revision,
+                header: summary.header,
+                score: summary.score,
+                review: summary.review,
+                forms,
+                rules
+            }
+        )
+    }
+
+    pub fn get_all_reports(conn: &Connection) -> Result<Vec<ReportSummary>> {
+        {
+            let mut stmt = conn.prepare("SELECT report.id, job_type_id, job_name, template_revision, engineer, job, day, month, year, score, pass_threshold, verdict,
+                                             review_state, reviewer, review_comments
+                                         FROM report
+                                         INNER JOIN report_header ON report_id = report.id
+                                         ORDER BY report.id")?;
+            let rows = stmt.query_map([], DBQualityControl::row_report_summary)?;
+
+            let mut reports = Vec::new();
+            for row in rows {
+                reports.push(row?);
+            }
+
+            Ok(reports)
+        }
+    }
+
+    // Stamps the report with its score, once it has been computed.
+    pub fn set_report_score(conn: &Connection, report_id: u64, score: &ReportScore) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("UPDATE report SET score = ?1, pass_threshold = ?2, verdict = ?3 WHERE id = ?4")?;
+            stmt.execute((score.score, score.pass_threshold, &score.verdict, report_id))
+        }
+    }
+
+    pub fn set_report_review(conn: &Connection, report_id: u64, review: &ReportReview) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("UPDATE report SET review_state = ?1, reviewer = ?2, review_comments = ?3 WHERE id = ?4")?;
+            stmt.execute((&review.state, &review.reviewer, &review.comments, report_id))
+        }
+    }
+
+    // Changes are appended after the ones already stored for the report.
+    pub fn add_report_history(conn: &Connection, report_id: u64, history: &[ReportFormChange]) -> Result<usize> {
+        let tx = conn.unchecked_transaction()?;
+        let count = DBQualityControl::insert_report_history(&tx, report_id, history)?;
+        tx.commit()?;
+
+        Ok(count)
+    }
+
+    // Run within the transaction of the caller.
+    fn insert_report_history(tx: &Connection, report_id: u64, history: &[ReportFormChange]) -> Result<usize> {
+        let mut stmt = tx.prepare("INSERT INTO report_form_change (report_id, form_id, timestamp, engineer, field, old_value, new_value) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+
+        for change in history {
+            stmt.execute((report_id, change.form_id, change.timestamp, &change.engineer, &change.field, &change.old_value, &change.new_value))?;
+        }
+
+        Ok(history.len())
+    }
+
+    // Oldest first.
+    pub fn get_report_history(conn: &Connection, report_id: u64) -> Result<Vec<ReportFormChange>> {
+        let mut stmt = conn.prepare("SELECT form_id, timestamp, engineer, field, old_value, new_value FROM report_form_change WHERE report_id = ?1 ORDER BY id")?;
+        let rows = stmt.query_map([report_id], |row| {
+            Ok(ReportFormChange::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        })?;
+
+        let mut history = Vec::new();
+        for row in rows {
+            history.push(row?);
+        }
+
+        Ok(history)
+    }
+
+    // Hashes of every attached file, to know which files of the attachment directory are still in use.
+    pub fn get_all_attachment_hashes(conn: &Connection) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT DISTINCT hash FROM report_attachment ORDER BY hash")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+
+        let mut hashes = Vec::new();
+        for row in rows {
+            hashes.push(row?);
+        }
+
+        Ok(hashes)
+    }
+
+    pub fn remove_report(conn: &Connection, report_id: u64) -> Result<usize> {
+        // Header and forms are removed through the cascade.
+        conn.execute("PRAGMA foreign_keys = 1", [])?;
+        {
+            let mut stmt = conn.prepare("DELETE FROM report WHERE id = ?1")?;
+            stmt.execute([report_id])
+        }
+    }
+
+    fn row_report_summary(row: &rusqlite::Row) -> Result<ReportSummary> {
+        let job_type_id = row.get_ref(1)?.as_i64_or_null()?.map(|id| u64::try_from(id).unwrap());
+        let template_revision = row.get_ref(3)?.as_i64_or_null()?.map(|rev| u64::try_from(rev).unwrap());
+        let score = match row.get::<_, Option<f64>>(9)? {
+            Some(score) => Some(ReportScore::new(score, row.get(10)?, row.get(11)?)),
+            None => None
+        };
+
+        Ok(
+            ReportSummary {
+                id: row.get(0)?,
+                job_type_id,
+                job_name: row.get(2)?,
+                template_revision,
+                header: ReportHeader {
+                    engineer: row.get(4)?,
+                    job: row.get(5)?,
+                    day: row.get(6)?,
+                    month: row.get(7)?,
+                    year: row.get(8)?,
+                },
+                score,
+                review: ReportReview::new(row.get(12)?, row.get(13)?, row.get(14)?),
+            }
+        )
+    }
+
+    pub fn get_job_template_revision(conn: &Connection, job_type_id: u64) -> Result<u64> {
+        conn.query_row("SELECT template_revision FROM job_type WHERE id = ?1", [job_type_id],
+                       |row| row.get(0))
+    }
+
+    /*
+     * Every change to what a job asks increases the revision of its template.
+     * Reports keep the revision they were answered against.
+     */
+    fn bump_job_revision(conn: &Connection, job_type_id: u64) -> Result<usize> {
+        conn.execute("UPDATE job_type SET template_revision = template_revision + 1 WHERE id = ?1", [job_type_id])
+    }
+
+    fn bump_specification_revision(conn: &Connection, specification_id: u64) -> Result<usize> {
+        conn.execute("UPDATE job_type SET template_revision = template_revision + 1 
+                      WHERE id IN (SELECT job_type_id FROM job_specification WHERE specification_id = ?1)",
+                      [specification_id])
+    }
+
+    fn bump_section_revision(conn: &Connection, section_id: u64) -> Result<usize> {
+        conn.execute("UPDATE job_type SET template_revision = template_revision + 1 
+                      WHERE id IN (SELECT job_type_id FROM job_specification 
+                                   INNER JOIN specification ON specification_id = specification.id 
+                                   WHERE section_id = ?1)",
+                      [section_id])
+    }
+
+    pub fn add_response_scale(conn: &Connection, scale_name: String) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("INSERT INTO response_scale (scale_name) VALUES (?1)")?;
+            stmt.execute([scale_name])
+        }
+    }
+
+    // Options are presented in the order they were added.
+    pub fn add_response_option(conn: &Connection, scale_id: u64, code: String, label: String, status: String) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("INSERT INTO response_option (scale_id, code, label, status, position)
+                                         SELECT ?1, ?2, ?3, ?4, COALESCE(MAX(position), 0) + 1 FROM response_option WHERE scale_id = ?1")?;
+            stmt.execute((scale_id, code, label, status))
+        }
+    }
+
+    pub fn remove_response_option(conn: &Connection, option_id: u64) -> Result<usize> {
+        {
+            let mut stmt = conn.prepare("DELETE FROM response_option WHERE id = ?1")?;
+            stmt.execute([option_id])
+        }
+    }
+
+    // Job types and specifications using the scale go back to the default one.
+    pub fn remove_response_scale(conn: &Connection, scale_id: u64) -> Result<usize> {
+        conn.execute("PRAGMA foreign_keys = 1", [])?;
+        {
+            let mut stmt = conn.prepare("DELETE FROM response_scale WHERE id = ?1")?;
+            stmt.execute([scale_id])
+        }
+    }
+
+    pub fn get_response_scale(conn: &Connection, scale_id: u64) -> Result<ResponseScale> {
+        let scale_name = conn.query_row("SELECT scale_name FROM response_scale WHERE id = ?1", [scale_id],
+                                        |row| row.get(0))?;
+
+        Ok(
+            ResponseScale {
+                id: scale_id,
+                scale_name,
+                options: DBQualityControl::get_response_options(conn, scale_id)?
+            }
+        )
+    }
+
+    pub fn get_all_response_scales(conn: &Connection) -> Result<Vec<ResponseScale>> {
+        let scales: Vec<(u64, String)> = {
+            let mut stmt = conn.prepare("SELECT id, scale_name FROM response_scale ORDER BY id")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+            let mut scales = Vec::new();
+            for row in rows {
+                scales.push(row?);
+            }
+            scales
+        };
+
+        let mut response_scales = Vec::new();
+        for (id, scale_name) in scales {
+            response_scales.push(ResponseScale { id, scale_name, options: DBQualityControl::get_response_options(conn, id)? });
+        }
+
+        Ok(response_scales)
+    }
+
+    fn get_response_options(conn: &Connection, scale_id: u64) -> Result<Vec<ResponseOption>> {
+        let mut stmt = conn.prepare("SELECT id, code, label, status, position FROM response_option WHERE scale_id = ?1 ORDER BY position")?;
+        let rows = stmt.query_map([scale_id], |row| Ok(
+                                            ResponseOption {
+                                                id: row.get(0)?,
+                                                code: row.get(1)?,
+                                                label: row.get(2)?,
+                                                status: row.get(3)?,
+                                                position: row.get(4)?,
+                                            }
+                                        ))?;
+
+        let mut options = Vec::new();
+        for row in rows {
+            options.push(row?);
+        }
+
+        Ok(options)
+    }
+
+    // *None* resets the job type to the default scale.
+    pub fn set_job_type_response_scale(conn: &Connection, job_type_id: u64, scale_id: Option<u64>) -> Result<usize> {
+        DBQualityControl::bump_job_revision(conn, job_type_id)?;
+        {
+            let mut stmt = conn.prepare("UPDATE job_type SET response_scale_id = ?1 WHERE id = ?2")?;
+            stmt.execute((scale_id, job_type_id))
+        }
+    }
+
+    // *None* makes the specification use the scale of the job type.
+    pub fn set_specification_response_scale(conn: &Connection, specification_id: u64, scale_id: Option<u64>) -> Result<usize> {
+        DBQualityControl::bump_specification_revision(conn, specification_id)?;
+        {
+            let mut stmt = conn.prepare("UPDATE specification SET response_scale_id = ?1 WHERE id = ?2")?;
+            stmt.execute((scale_id, specification_id))
+        }
+    }
+
+    pub fn get_job_type_response_scale(conn: &Connection, job_type_id: u64) -> Result<Option<u64>> {
+        conn.query_row("SELECT response_scale_id FROM job_type WHERE id = ?1", [job_type_id],
+                       |row| row.get(0))
+    }
+
+    // Pairs of specification id and scale id, for the specifications that override the scale of their job.
+    pub fn get_all_specification_response_scales(conn: &Connection) -> Result<Vec<(u64, u64)>> {
+        let mut stmt = conn.prepare("SELECT id, response_scale_id FROM specification WHERE response_scale_id IS NOT NULL ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut scales = Vec::new();
+        for row in rows {
+            scales.push(row?);
+        }
+
+        Ok(scales)
+    }
+
+    // *None* turns the specification back into a checklist item.
+    pub fn set_specification_measurement(conn: &Connection, specification_id: u64, measurement: Option<&Measurement>) -> Result<usize> {
+        DBQualityControl::bump_specification_revision(conn, specification_id)?;
+
+        match measurement {
+            Some(m) => conn.execute("INSERT OR REPLACE INTO specification_measurement (specification_id, unit, nominal_value, min_value, max_value)
+                                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                                     (specification_id, &m.unit, m.nominal_value, m.min_value, m.max_value)),
+            None    => conn.execute("DELETE FROM specification_measurement WHERE specification_id = ?1", [specification_id]),
+        }
+    }
+
+    pub fn get_specification_measurement(conn: &Connection, specification_id: u64) -> Result<Option<Measurement>> {
+        match conn.query_row("SELECT unit, nominal_value, min_value, max_value FROM specification_measurement WHERE specification_id = ?1",
+                             [specification_id],
+                             |row| Ok(Measurement::new(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))) {
+            Ok(measurement) => Ok(Some(measurement)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    // Pairs of specification id and measurement, for every measured specification.
+    pub fn get_all_specification_measurements(conn: &Connection) -> Result<Vec<(u64, Measurement)>> {
+        let mut stmt = conn.prepare("SELECT specification_id, unit, nominal_value, min_value, max_value FROM specification_measurement ORDER BY specification_id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, Measurement::new(row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))))?;
+
+        let mut measurements = Vec::new();
+        for row in rows {
+            measurements.push(row?);
+        }
+
+        Ok(measurements)
+    }
+
+    // A weight of 0 leaves the specification out of the score.
+    pub fn set_specification_weight(conn: &Connection, specification_id: u64, weight: u32) -> Result<usize> {
+        DBQualityControl::bump_specification_revision(conn, specification_id)?;
+        {
+            let mut stmt = conn.prepare("UPDATE specification SET weight = ?1 WHERE id = ?2")?;
+            stmt.execute((weight, specification_id))
+        }
+    }
+
+    // Pairs of specification id and weight, for the specifications that do not have the default weight of 1.
+    pub fn get_all_specification_weights(conn: &Connection) -> Result<Vec<(u64, u32)>> {
+        let mut stmt = conn.prepare("SELECT id, weight FROM specification WHERE weight != 1 ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut weights = Vec::new();
+        for row in rows {
+            weights.push(row?);
+        }
+
+        Ok(weights)
+    }
+
+    // Minimum score, in percent, for a report of the job type to pass. *None* means reports are not judged.
+    pub fn set_job_type_pass_threshold(conn: &Connection, job_type_id: u64, pass_threshold: Option<f64>) -> Result<usize> {
+        DBQualityControl::bump_job_revision(conn, job_type_id)?;
+        {
+            let mut stmt = conn.prepare("UPDATE job_type SET pass_threshold = ?1 WHERE id = ?2")?;
+            stmt.execute((pass_threshold, job_type_id))
+        }
+    }
+
+    pub fn get_job_type_pass_threshold(conn: &Connection, job_type_id: u64) -> Result<Option<f64>> {
+        conn.query_row("SELECT pass_threshold FROM job_type WHERE id = ?1", [job_type_id],
+                       |row| row.get(0))
+    }
+
+    // The id of *rule* is ignored.
+    pub fn add_specification_rule(conn: &Connection, rule: &SpecificationRule) -> Result<usize> {
+        DBQualityControl::bump_specification_revision(conn, rule.depends_on_specification_id)?;
+        {
+            let mut stmt = conn.prepare("INSERT INTO specification_rule (target_specification_id, target_section_id, depends_on_specification_id, status, action)
+                                         VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            stmt.execute((rule.target_specification_id, rule.target_section_id, rule.depends_on_specification_id, &rule.status, &rule.action))
+        }
+    }
+
+    pub fn remove_specification_rule(conn: &Connection, rule_id: u64) -> Result<usize> {
+        let depends_on: u64 = conn.query_row("SELECT depends_on_specification_id FROM specification_rule WHERE id = ?1", [rule_id],
+                                             |row| row.get(0))?;
+        DBQualityControl::bump_specification_revision(conn, depends_on)?;
+        {
+            let mut stmt = conn.prepare("DELETE FROM specification_rule WHERE id = ?1")?;
+            stmt.execute([rule_id])
+        }
+    }
+
+    pub fn get_all_specification_rules(conn: &Connection) -> Result<Vec<SpecificationRule>> {
+        let mut stmt = conn.prepare("SELECT id, target_specification_id, target_section_id, depends_on_specification_id, status, action
+                                     FROM specification_rule ORDER BY id")?;
+        let rows = stmt.query_map([], DBQualityControl::row_specification_rule)?;
+
+        let mut rules = Vec::new();
+        for row in rows {
+            rules.push(row?);
+        }
+
+        Ok(rules)
+    }
+
+    fn row_specification_rule(row: &rusqlite::Row) -> Result<SpecificationRule> {
+        Ok(
+            SpecificationRule {
+                id: row.get(0)?,
+                target_specification_id: row.get(1)?,
+                target_section_id: row.get(2)?,
+                depends_on_specification_id: row.get(3)?,
+                status: row.get(4)?,
+                action: row.get(5)?,
+            }
+        )
+    }
+}
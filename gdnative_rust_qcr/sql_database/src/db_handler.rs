@@ -1,5 +1,6 @@
-use rusqlite::{params, Connection, Result};
-use crate::db::{DBQualityControl,JobType,Section, Specification, JobSpecificationSection};
+use rusqlite::{Connection, Result};
+use crate::db::{DBQualityControl,JobType,Section, Specification, JobSpecificationSection, Report, ReportRecord, ReportSummary, ResponseScale, Measurement, ReportScore, SpecificationRule, ReportFormChange, ReportReview};
+use crate::error::{DbError, Entity, Key};
 
 
 macro_rules! db_apply {
@@ -9,15 +10,16 @@ macro_rules! db_apply {
         let conn = Connection::open($path);
 
         if let Err(e) = conn {
-            return Err(vec![e]);
+            return Err(DbError::Connection(e));
         }
 
         let conn = conn.unwrap();
 
+        // Foreign keys are off by default, and only apply to the connection that enables them.
+        if let Err(e) = conn.execute("PRAGMA foreign_keys = 1", []) {
+            return Err(DbError::Connection(e));
+        }
 
-        let mut errors = Vec::new();
-        
-        
         // stores a generic value.
         let result = $f(&conn, // repetedly fill in the arguments.
         $(
@@ -27,28 +29,11 @@ macro_rules! db_apply {
         );
 
 
-        // Need to figure out how to close the connection
-        let mut conn_open:Option<Connection> = None;
-        if let Err((conn,e)) = conn.close() {
+        // The outer error is a failed close, it takes precedence over the result of the query.
+        match conn.close() {
             // include the connection to figure how to close it.
-            conn_open = Some(conn);
-            errors.push(e);
-        }
-
-
-        if errors.len() > 0 {
-            Err((conn_open, errors))
-        } else {    // 
-                    // begin testing result status, to return generic value
-            match result {
-                Ok(value) => Ok(value),
-                Err(e) => {
-                    errors.push(e);
-                    Err((conn_open, errors))
-                }
-            }
-
-
+            Err((conn, e)) => Err((conn, e)),
+            Ok(()) => Ok(result),
         }
         }
         
@@ -68,255 +53,333 @@ impl DBQualityControlHandle {
         Self { database_path, connection: None }
     }
 
-    fn db_apply(&mut self, entry_name: String, f: &dyn Fn(&Connection, String) -> Result<usize>) -> Result<(), Vec<rusqlite::Error>> {
-        let conn = Connection::open(&self.database_path);
+    pub fn initialize(&mut self) -> Result<u32, DbError> {
+        let result = db_apply!(DBQualityControl::initialize, &self.database_path,);
+        self.handle_query(result, Entity::Schema, Key::Any)
+    }
 
-        if let Err(e) = conn {
-            return Err(vec![e]);
-        }
+    pub fn schema_version(&mut self) -> Result<u32, DbError> {
+        let result = db_apply!(DBQualityControl::schema_version, &self.database_path,);
+        self.handle_query(result, Entity::Schema, Key::Any)
+    }
 
-        let conn = conn.unwrap();
+    pub fn job_has_specification(&mut self, job_type_id: u64, specification_id: u64) -> Result<bool, DbError> {
+        let result = db_apply!(DBQualityControl::job_has_specification, &self.database_path, job_type_id, specification_id);
+        self.handle_query(result, Entity::JobSpecification, Key::Id(specification_id))
+    }
 
+    pub fn job_exists(&mut self, job_type_name: String) -> Result<bool, DbError> {
+        let key = Key::Name(job_type_name.clone());
+        let result = db_apply!(DBQualityControl::job_exists, &self.database_path, job_type_name);
+        self.handle_query(result, Entity::JobType, key)
+    }
 
-        let mut errors = Vec::new();
+    pub fn add_section(&mut self, section_name: String) -> Result<(), DbError> {
+        let key = Key::Name(section_name.clone());
+        let result = db_apply!(DBQualityControl::add_section, &self.database_path, section_name);
+        self.handle_query(result, Entity::Section, key).map(|_| ())
+    }
 
-        // Figure out how to have dynamic parameters.
-        // That way we only use one function.
-        if let Err(e) = f(&conn, entry_name) {
-            errors.push(e);
-        }
+    pub fn add_job_type(&mut self, job_type_name: String) -> Result<(), DbError> {
+        let key = Key::Name(job_type_name.clone());
+        let result = db_apply!(DBQualityControl::add_job_type, &self.database_path, job_type_name);
+        self.handle_query(result, Entity::JobType, key).map(|_| ())
+    }
 
-        // Need to figure out how to close the connection
-        if let Err((conn,e)) = conn.close() {
-            // include the connection to figure how to close it.
-            self.connection = Some(conn);
-            errors.push(e);
-        }
+    pub fn add_specification(&mut self, specification_name: String, section_id: Option<u64>) -> Result<(), DbError> {
+        let key = Key::Name(specification_name.clone());
+        let result = db_apply!(DBQualityControl::add_specification, &self.database_path, specification_name, section_id);
+        self.handle_query(result, Entity::Specification, key).map(|_| ())
+    }
 
-        if errors.len() > 0 {
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn add_additive_section(&mut self, additive_section_name: String) -> Result<(), DbError> {
+        let key = Key::Name(additive_section_name.clone());
+        let result = db_apply!(DBQualityControl::add_additive_section, &self.database_path, additive_section_name);
+        self.handle_query(result, Entity::AdditiveSection, key).map(|_| ())
     }
 
-    pub fn job_has_specification(&mut self, job_type_id: u64, specification_id: u64) -> Result<bool, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::job_has_specification, &self.database_path, job_type_id, specification_id);
+    pub fn remove_additive_section(&mut self, section_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_additive_section, &self.database_path, section_id);
+        self.handle_update(result, Entity::AdditiveSection, Key::Id(section_id), section_id)
+    }
 
-        self.handle_query(result)
+    pub fn get_all_additive_sections(&mut self) -> Result<Vec<Section>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_additive_sections, &self.database_path,);
+        self.handle_query(result, Entity::AdditiveSection, Key::Any)
     }
 
-    pub fn job_exists(&mut self, job_type_name: String) -> Result<bool, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::job_exists, &self.database_path, job_type_name);
+    pub fn get_section_specifications(&mut self, section_id: u64) -> Result<Vec<Specification>, DbError> {
+        let result = db_apply!(DBQualityControl::get_section_specifications, &self.database_path, section_id);
+        self.handle_query(result, Entity::Section, Key::Id(section_id))
+    }
 
-        self.handle_query(result)
+    pub fn add_job_specification(&mut self, job_type_id: u64, specification_id: u64) -> Result<(), DbError> {
+        let result = db_apply!(DBQualityControl::add_job_specification, &self.database_path, job_type_id, specification_id);
+        self.handle_query(result, Entity::JobSpecification, Key::Id(specification_id)).map(|_| ())
     }
 
-    pub fn add_section(&mut self, section_name: String) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn update_section(&mut self, section_id: u64, section_name: String) -> Result<(), DbError> {
+        let key = Key::Name(section_name.clone());
+        let result = db_apply!(DBQualityControl::update_section, &self.database_path, section_id, section_name);
+        self.handle_update(result, Entity::Section, key, section_id).map(|_| ())
+    }
 
-        if let Err((op_conn,errors)) = db_apply!(DBQualityControl::add_section,path, section_name)  {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn get_section(&mut self, section_id: u64) -> Result<Section, DbError> {
+        let result = db_apply!(DBQualityControl::get_section, &self.database_path, section_id);
+        self.handle_query(result, Entity::Section, Key::Id(section_id))
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn update_job_type(&mut self, job_type_id: u64, job_type_name: String) -> Result<(), DbError> {
+        let key = Key::Name(job_type_name.clone());
+        let result = db_apply!(DBQualityControl::update_job_type, &self.database_path, job_type_id, job_type_name);
+        self.handle_update(result, Entity::JobType, key, job_type_id).map(|_| ())
     }
 
-    pub fn add_job_type(&mut self, job_type_name: String) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn get_job_type_id(&mut self, job_type_name: String) -> Result<u64, DbError> {
+        let key = Key::Name(job_type_name.clone());
+        let result = db_apply!(DBQualityControl::get_job_type_id, &self.database_path, job_type_name);
+        self.handle_query(result, Entity::JobType, key)
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::add_job_type, path, job_type_name) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn get_job_type(&mut self, job_type_id: u64) -> Result<JobType, DbError> {
+        let result = db_apply!(DBQualityControl::get_job_type, &self.database_path, job_type_id);
+        self.handle_query(result, Entity::JobType, Key::Id(job_type_id))
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
-        //self.db_apply(job_type_name, &DBQualityControl::add_job_type)
+    pub fn update_specification_content(&mut self, specification_id: u64, specification_name: String) -> Result<usize, DbError> {
+        let key = Key::Name(specification_name.clone());
+        let result = db_apply!(DBQualityControl::update_specification_content, &self.database_path, specification_id, specification_name);
+        self.handle_update(result, Entity::Specification, key, specification_id)
     }
 
-    pub fn add_specification(&mut self, specification_name: String, section_id: Option<u64>) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn get_specification(&mut self, specification_id: u64) -> Result<Specification, DbError> {
+        let result = db_apply!(DBQualityControl::get_specification, &self.database_path, specification_id);
+        self.handle_query(result, Entity::Specification, Key::Id(specification_id))
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::add_specification, path, specification_name, section_id) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn get_all_job_specification(&mut self, job_type_id: u64) -> Result<Vec<JobSpecificationSection>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_job_specification, &self.database_path, job_type_id);
+        self.handle_query(result, Entity::JobType, Key::Id(job_type_id))
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn get_all_job_types(&mut self) -> Result<Vec<JobType>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_job_types, &self.database_path,);
+        self.handle_query(result, Entity::JobType, Key::Any)
     }
 
-    pub fn add_additive_section(&mut self, additive_section_name: String) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn get_all_sections(&mut self) -> Result<Vec<Section>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_sections, &self.database_path,);
+        self.handle_query(result, Entity::Section, Key::Any)
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::add_additive_section, path, additive_section_name) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn get_all_specifications(&mut self) -> Result<Vec<Specification>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_specifications, &self.database_path,);
+        self.handle_query(result, Entity::Specification, Key::Any)
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn update_specification_section(&mut self, specification_id: u64, section_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::update_specification_section, &self.database_path, specification_id, section_id);
+        self.handle_update(result, Entity::Specification, Key::Id(specification_id), specification_id)
     }
 
-    pub fn add_job_specification(&mut self, job_type_id: u64, specification_id: u64) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn remove_specification_section(&mut self, specification_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_specification_section, &self.database_path, specification_id);
+        self.handle_update(result, Entity::Specification, Key::Id(specification_id), specification_id)
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::add_job_specification, path, job_type_id, specification_id) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn remove_specification(&mut self, specification_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_specification, &self.database_path, specification_id);
+        self.handle_update(result, Entity::Specification, Key::Id(specification_id), specification_id)
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn remove_section(&mut self, section_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_section, &self.database_path, section_id);
+        self.handle_update(result, Entity::Section, Key::Id(section_id), section_id)
     }
 
-    pub fn update_section(&mut self, section_id: u64, section_name: String) -> Result<(), Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn remove_job_type(&mut self, job_type_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_job_type, &self.database_path, job_type_id);
+        self.handle_update(result, Entity::JobType, Key::Id(job_type_id), job_type_id)
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::update_section, path,section_id, section_name) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn remove_job_spec(&mut self, job_type_id: u64, specification_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_job_spec, &self.database_path, job_type_id, specification_id);
+        self.handle_update(result, Entity::JobSpecification, Key::Id(specification_id), specification_id)
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn save_report(&mut self, job_type_id: Option<u64>, record: &ReportRecord) -> Result<u64, DbError> {
+        let result = db_apply!(DBQualityControl::save_report, &self.database_path, job_type_id, record);
+        self.handle_query(result, Entity::Report, Key::Any)
+    }
 
+    pub fn update_report(&mut self, report_id: u64, record: &ReportRecord) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::update_report, &self.database_path, report_id, record);
+        self.handle_update(result, Entity::Report, Key::Id(report_id), report_id)
     }
 
-    pub fn get_section(&mut self, section_id: u64) -> Result<Section, Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn get_report(&mut self, report_id: u64) -> Result<Report, DbError> {
+        let result = db_apply!(DBQualityControl::get_report, &self.database_path, report_id);
+        self.handle_query(result, Entity::Report, Key::Id(report_id))
+    }
 
-        match db_apply!(DBQualityControl::get_section, path, section_id) {
-            Ok(section) => Ok(section), 
-            Err((conn, errors)) => {
-                if let Some(conn) = conn {
-                    self.connection = Some(conn);
-                }
-                Err(errors)
-            }
-        }
+    pub fn get_all_reports(&mut self) -> Result<Vec<ReportSummary>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_reports, &self.database_path,);
+        self.handle_query(result, Entity::Report, Key::Any)
+    }
 
+    pub fn get_job_template_revision(&mut self, job_type_id: u64) -> Result<u64, DbError> {
+        let result = db_apply!(DBQualityControl::get_job_template_revision, &self.database_path, job_type_id);
+        self.handle_query(result, Entity::JobType, Key::Id(job_type_id))
     }
 
-    pub fn update_job_type(&mut self, job_type_id: u64, job_type_name: String) -> Result<(), Vec<rusqlite::Error>> {
+    pub fn remove_report(&mut self, report_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_report, &self.database_path, report_id);
+        self.handle_update(result, Entity::Report, Key::Id(report_id), report_id)
+    }
 
-        let path = &self.database_path.clone();
+    pub fn add_response_scale(&mut self, scale_name: String) -> Result<usize, DbError> {
+        let key = Key::Name(scale_name.clone());
+        let result = db_apply!(DBQualityControl::add_response_scale, &self.database_path, scale_name);
+        self.handle_query(result, Entity::ResponseScale, key)
+    }
 
-        if let Err((op_conn, errors)) = db_apply!(DBQualityControl::update_job_type, path,job_type_id, job_type_name) {
-            if let Some(conn) = op_conn {
-                self.connection = Some(conn);
-            }
+    pub fn add_response_option(&mut self, scale_id: u64, code: String, label: String, status: String) -> Result<usize, DbError> {
+        let key = Key::Name(code.clone());
+        let result = db_apply!(DBQualityControl::add_response_option, &self.database_path, scale_id, code, label, status);
+        self.handle_query(result, Entity::ResponseOption, key)
+    }
 
-            Err(errors)
-        } else {
-            Ok(())
-        }
+    pub fn remove_response_option(&mut self, option_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_response_option, &self.database_path, option_id);
+        self.handle_update(result, Entity::ResponseOption, Key::Id(option_id), option_id)
     }
 
-    pub fn get_job_type_id(&mut self, job_type_name: String) -> Result<u64, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::get_job_type_id, &self.database_path, job_type_name);
+    pub fn remove_response_scale(&mut self, scale_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_response_scale, &self.database_path, scale_id);
+        self.handle_update(result, Entity::ResponseScale, Key::Id(scale_id), scale_id)
+    }
 
-        self.handle_query(result)
+    pub fn get_response_scale(&mut self, scale_id: u64) -> Result<ResponseScale, DbError> {
+        let result = db_apply!(DBQualityControl::get_response_scale, &self.database_path, scale_id);
+        self.handle_query(result, Entity::ResponseScale, Key::Id(scale_id))
+    }
 
+    pub fn get_all_response_scales(&mut self) -> Result<Vec<ResponseScale>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_response_scales, &self.database_path,);
+        self.handle_query(result, Entity::ResponseScale, Key::Any)
     }
 
-    pub fn get_job_type(&mut self, job_type_id: u64) -> Result<JobType, Vec<rusqlite::Error>> {
-        let path = &self.database_path.clone();
+    pub fn set_job_type_response_scale(&mut self, job_type_id: u64, scale_id: Option<u64>) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_job_type_response_scale, &self.database_path, job_type_id, scale_id);
+        self.handle_update(result, Entity::JobType, Key::Id(job_type_id), job_type_id)
+    }
 
-        match db_apply!(DBQualityControl::get_job_type, path, job_type_id) {
-            Ok(job_type) => Ok(job_type), 
-            Err((conn, errors)) => {
-                if let Some(conn) = conn {
-                    self.connection = Some(conn);
-                }
-                Err(errors)
-            }
-        }
+    pub fn set_specification_response_scale(&mut self, specification_id: u64, scale_id: Option<u64>) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_specification_response_scale, &self.database_path, specification_id, scale_id);
+        self.handle_update(result, Entity::Specification, Key::Id(specification_id), specification_id)
     }
 
-    pub fn update_specification_content(&mut self, specification_id: u64, specification_name: String) -> Result<usize, Vec<rusqlite::Error>> {
+    pub fn get_job_type_response_scale(&mut self, job_type_id: u64) -> Result<Option<u64>, DbError> {
+        let result = db_apply!(DBQualityControl::get_job_type_response_scale, &self.database_path, job_type_id);
+        self.handle_query(result, Entity::JobType, Key::Id(job_type_id))
+    }
 
-        let result = db_apply!(DBQualityControl::update_specification_content, &self.database_path, specification_id, specification_name) ;
-        self.handle_query(result)
+    pub fn get_all_specification_response_scales(&mut self) -> Result<Vec<(u64, u64)>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_specification_response_scales, &self.database_path,);
+        self.handle_query(result, Entity::ResponseScale, Key::Any)
     }
 
-    pub fn get_specification(&mut self, specification_id: u64) -> Result<Specification, Vec<rusqlite::Error>> {
+    pub fn set_specification_measurement(&mut self, specification_id: u64, measurement: Option<&Measurement>) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_specification_measurement, &self.database_path, specification_id, measurement);
+        self.handle_query(result, Entity::Measurement, Key::Id(specification_id))
+    }
 
-        let result = db_apply!(DBQualityControl::get_specification, &self.database_path, specification_id);
-        self.handle_query(result)
+    pub fn get_specification_measurement(&mut self, specification_id: u64) -> Result<Option<Measurement>, DbError> {
+        let result = db_apply!(DBQualityControl::get_specification_measurement, &self.database_path, specification_id);
+        self.handle_query(result, Entity::Measurement, Key::Id(specification_id))
     }
 
-    pub fn get_all_job_specification(&mut self, job_type_id: u64) -> Result<Vec<JobSpecificationSection>, Vec<rusqlite::Error>> {
+    pub fn get_all_specification_measurements(&mut self) -> Result<Vec<(u64, Measurement)>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_specification_measurements, &self.database_path,);
+        self.handle_query(result, Entity::Measurement, Key::Any)
+    }
 
-        let result = db_apply!(DBQualityControl::get_all_job_specification, &self.database_path, job_type_id);
-        self.handle_query(result)
+    pub fn set_report_score(&mut self, report_id: u64, score: &ReportScore) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_report_score, &self.database_path, report_id, score);
+        self.handle_update(result, Entity::Report, Key::Id(report_id), report_id)
     }
 
-    pub fn get_all_job_types(&mut self) -> Result<Vec<JobType>, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::get_all_job_types, &self.database_path,);
-        self.handle_query(result)
+    pub fn set_specification_weight(&mut self, specification_id: u64, weight: u32) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_specification_weight, &self.database_path, specification_id, weight);
+        self.handle_update(result, Entity::Specification, Key::Id(specification_id), specification_id)
     }
 
-    pub fn get_all_sections(&mut self) -> Result<Vec<Section>, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::get_all_sections, &self.database_path,);
-        self.handle_query(result)
+    pub fn get_all_specification_weights(&mut self) -> Result<Vec<(u64, u32)>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_specification_weights, &self.database_path,);
+        self.handle_query(result, Entity::Specification, Key::Any)
     }
 
-    pub fn get_all_specifications(&mut self) -> Result<Vec<Specification>, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::get_all_specifications, &self.database_path,);
-        self.handle_query(result)
+    pub fn set_job_type_pass_threshold(&mut self, job_type_id: u64, pass_threshold: Option<f64>) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_job_type_pass_threshold, &self.database_path, job_type_id, pass_threshold);
+        self.handle_update(result, Entity::JobType, Key::Id(job_type_id), job_type_id)
     }
 
-    pub fn update_specification_section(&mut self, specification_id: u64, section_id: u64) -> Result<usize, Vec<rusqlite::Error>> {
+    pub fn get_job_type_pass_threshold(&mut self, job_type_id: u64) -> Result<Option<f64>, DbError> {
+        let result = db_apply!(DBQualityControl::get_job_type_pass_threshold, &self.database_path, job_type_id);
+        self.handle_query(result, Entity::JobType, Key::Id(job_type_id))
+    }
 
-        let result = db_apply!(DBQualityControl::update_specification_section, &self.database_path, specification_id, section_id);
-        self.handle_query(result)
+    pub fn add_specification_rule(&mut self, rule: &SpecificationRule) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::add_specification_rule, &self.database_path, rule);
+        self.handle_query(result, Entity::SpecificationRule, Key::Any)
     }
 
-    pub fn remove_specification(&mut self, specification_id: u64) -> Result<usize, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::remove_specification, &self.database_path, specification_id);
-        self.handle_query(result)
+    pub fn remove_specification_rule(&mut self, rule_id: u64) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::remove_specification_rule, &self.database_path, rule_id);
+        self.handle_query(result, Entity::SpecificationRule, Key::Id(rule_id))
     }
 
-    pub fn remove_section(&mut self, section_id: u64) -> Result<usize, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::remove_section,&self.database_path, section_id);
-        self.handle_query(result)
+    pub fn get_all_specification_rules(&mut self) -> Result<Vec<SpecificationRule>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_specification_rules, &self.database_path,);
+        self.handle_query(result, Entity::SpecificationRule, Key::Any)
     }
 
-    pub fn remove_job_type(&mut self, job_type_id: u64) -> Result<usize, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::remove_job_type, &self.database_path, job_type_id);
-        self.handle_query(result)
+    pub fn set_report_review(&mut self, report_id: u64, review: &ReportReview) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::set_report_review, &self.database_path, report_id, review);
+        self.handle_update(result, Entity::Report, Key::Id(report_id), report_id)
     }
 
-    pub fn remove_job_spec(&mut self, job_type_id: u64, specification_id: u64) ->Result<usize, Vec<rusqlite::Error>> {
-        let result = db_apply!(DBQualityControl::remove_job_spec, &self.database_path, job_type_id, specification_id);
-        self.handle_query(result)
+    pub fn add_report_history(&mut self, report_id: u64, history: &[ReportFormChange]) -> Result<usize, DbError> {
+        let result = db_apply!(DBQualityControl::add_report_history, &self.database_path, report_id, history);
+        self.handle_query(result, Entity::Report, Key::Id(report_id))
     }
-    
+
+    pub fn get_report_history(&mut self, report_id: u64) -> Result<Vec<ReportFormChange>, DbError> {
+        let result = db_apply!(DBQualityControl::get_report_history, &self.database_path, report_id);
+        self.handle_query(result, Entity::Report, Key::Id(report_id))
+    }
+
+    pub fn get_all_attachment_hashes(&mut self) -> Result<Vec<String>, DbError> {
+        let result = db_apply!(DBQualityControl::get_all_attachment_hashes, &self.database_path,);
+        self.handle_query(result, Entity::Attachment, Key::Any)
+    }
+
+    // UPDATE and DELETE statements succeed without any row with the id, they are not found instead.
+    fn handle_update(&mut self, result: std::result::Result<Result<usize>, (Connection, rusqlite::Error)>, entity: Entity, key: Key, id: u64) -> Result<usize, DbError> {
+        match self.handle_query(result, entity, key)? {
+            0 => Err(DbError::NotFound { entity, key: Key::Id(id) }),
+            count => Ok(count),
+        }
+    }
+
     // Boiler plate code that deals with a database that failed to closed.
-    fn handle_query<T>(&mut self, result: Result<T,(Option<Connection>,Vec<rusqlite::Error>)>) -> Result<T, Vec<rusqlite::Error>> {
+    fn handle_query<T>(&mut self, result: std::result::Result<Result<T>, (Connection, rusqlite::Error)>, entity: Entity, key: Key) -> Result<T, DbError> {
         match result {
-            Ok(value) => Ok(value),
-            Err((conn, errors)) => { 
-                if let Some(conn) = conn {
-                    self.connection = Some(conn);
-                }
-                Err(errors)
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(DbError::from_query(e, entity, key)),
+            Err((conn, e)) => {
+                self.connection = Some(conn);
+                Err(DbError::Close(e))
             }
         }
     }
@@ -0,0 +1,155 @@
+/*
+ * Errors of the database handle.
+ *
+ * SQLite errors are sorted into the failures callers react to, and carry the
+ * entity and the id or name the query was about, e.g. to tell the user
+ * "A job type named "Cement" already exists" instead of a generic failure.
+ */
+use rusqlite::ffi;
+
+use std::error::Error;
+use std::fmt::Display;
+
+// Extended result codes of constraint violations, not exported by rusqlite.
+const SQLITE_CONSTRAINT_FOREIGNKEY: i32 = ffi::SQLITE_CONSTRAINT | (3 << 8);
+const SQLITE_CONSTRAINT_PRIMARYKEY: i32 = ffi::SQLITE_CONSTRAINT | (6 << 8);
+const SQLITE_CONSTRAINT_UNIQUE: i32 = ffi::SQLITE_CONSTRAINT | (8 << 8);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Entity {
+    Schema,
+    JobType,
+    Section,
+    AdditiveSection,
+    Specification,
+    JobSpecification,
+    Report,
+    ResponseScale,
+    ResponseOption,
+    Measurement,
+    SpecificationRule,
+    Attachment,
+}
+
+impl Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Entity::Schema => "database schema",
+            Entity::JobType => "job type",
+            Entity::Section => "section",
+            Entity::AdditiveSection => "additive section",
+            Entity::Specification => "specification",
+            Entity::JobSpecification => "job specification",
+            Entity::Report => "report",
+            Entity::ResponseScale => "response scale",
+            Entity::ResponseOption => "response option",
+            Entity::Measurement => "measurement",
+            Entity::SpecificationRule => "specification rule",
+            Entity::Attachment => "attachment",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// What the query was about.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Key {
+    Id(u64),
+    Name(String),
+    // Queries over every row of a table.
+    Any,
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Id(id) => write!(f, " with id {}", id),
+            Key::Name(name) => write!(f, " named \"{}\"", name),
+            Key::Any => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DbError {
+    // The database could not be opened.
+    Connection(rusqlite::Error),
+    // The query ran, but the connection could not be closed. The handle keeps it.
+    Close(rusqlite::Error),
+    NotFound { entity: Entity, key: Key },
+    // Unique constraint, e.g. a section name that is already used.
+    Duplicate { entity: Entity, key: Key },
+    // Foreign key constraint, e.g. a job specification for a job type that does not exist.
+    ForeignKey { entity: Entity, key: Key },
+    Query { entity: Entity, key: Key, source: rusqlite::Error },
+}
+
+impl DbError {
+    // Sorts the error of a query about *entity*.
+    pub fn from_query(error: rusqlite::Error, entity: Entity, key: Key) -> Self {
+        match &error {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound { entity, key },
+            rusqlite::Error::SqliteFailure(e, _) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+                match e.extended_code {
+                    SQLITE_CONSTRAINT_UNIQUE | SQLITE_CONSTRAINT_PRIMARYKEY => DbError::Duplicate { entity, key },
+                    SQLITE_CONSTRAINT_FOREIGNKEY => DbError::ForeignKey { entity, key },
+                    _ => DbError::Query { entity, key, source: error },
+                }
+            },
+            _ => DbError::Query { entity, key, source: error },
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, DbError::NotFound { .. })
+    }
+
+    pub fn is_duplicate(&self) -> bool {
+        matches!(self, DbError::Duplicate { .. })
+    }
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::Connection(e) => write!(f, "Unable to open the database: {}", e),
+            DbError::Close(e) => write!(f, "Unable to close the database: {}", e),
+            DbError::NotFound { entity, key } => write!(f, "No {}{} found", entity, key),
+            DbError::Duplicate { entity, key } => write!(f, "{} {}{} already exists", article(*entity), entity, key),
+            DbError::ForeignKey { entity, key } => write!(f, "The {}{} refers to something that does not exist", entity, key),
+            DbError::Query { entity, key, source } => write!(f, "Failed to access {}{}: {}", entity, key, source),
+        }
+    }
+}
+
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::Connection(e) | DbError::Close(e) | DbError::Query { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn article(entity: Entity) -> &'static str {
+    match entity.to_string().chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => "An",
+        _ => "A",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_context() {
+        let duplicate = DbError::Duplicate { entity: Entity::JobType, key: Key::Name(String::from("Cement")) };
+        assert_eq!("A job type named \"Cement\" already exists", duplicate.to_string());
+
+        let missing = DbError::from_query(rusqlite::Error::QueryReturnedNoRows, Entity::AdditiveSection, Key::Id(3));
+        assert_eq!("No additive section with id 3 found", missing.to_string());
+        assert!(missing.is_not_found());
+    }
+}
@@ -1,2 +1,4 @@
 pub mod db;
 pub mod db_handler;
+pub mod error;
+pub mod migrations;
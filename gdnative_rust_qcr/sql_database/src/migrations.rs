@@ -0,0 +1,362 @@
+/*
+ * Schema of the quality control database.
+ *
+ * The version of a database is stored in SQLite's *user_version*.
+ * Each step brings the schema from version *n* to *n + 1*, and is written so that
+ * it can also be applied to databases created before versioning existed (version 0).
+ */
+use rusqlite::{Connection, Result};
+
+type Migration = fn(&Connection) -> Result<()>;
+
+// Ordered, step *i* upgrades the schema to version *i + 1*.
+const MIGRATIONS: &[Migration] = &[
+    create_templates,
+    create_reports,
+    unique_additive_sections,
+    create_response_scales,
+    create_measurements,
+    create_scoring,
+    create_specification_rules,
+    create_report_attachments,
+    create_report_history,
+    add_report_review,
+    create_report_response_scales,
+    create_report_rules,
+];
+
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// Applies every step between the current version and *target_version*.
+pub fn migrate(conn: &Connection, target_version: u32) -> Result<u32> {
+    let current_version = schema_version(conn)?;
+
+    if current_version > LATEST_VERSION {
+        // Database written by a newer version of the application.
+        return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+                Some(format!("Database schema version {} is newer than the supported version {}", current_version, LATEST_VERSION))));
+    }
+
+    for version in current_version..target_version.min(LATEST_VERSION) {
+        // A failed step leaves the database at the previous version.
+        let tx = conn.unchecked_transaction()?;
+
+        MIGRATIONS[version as usize](&tx)?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+
+        tx.commit()?;
+    }
+
+    schema_version(conn)
+}
+
+// Removes every table, leaving an empty database at version 0.
+pub fn drop_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        BEGIN;
+        DROP TABLE IF EXISTS report_rule;
+        DROP TABLE IF EXISTS report_form_option;
+        DROP TABLE IF EXISTS report_form_change;
+        DROP TABLE IF EXISTS report_attachment;
+        DROP TABLE IF EXISTS report_unit_form;
+        DROP TABLE IF EXISTS report_header;
+        DROP TABLE IF EXISTS report;
+        DROP TABLE IF EXISTS section;
+        DROP TABLE IF EXISTS additive_section;
+        DROP TABLE IF EXISTS job_type;
+        DROP TABLE IF EXISTS specification;
+        DROP TABLE IF EXISTS job_specification;
+        DROP TABLE IF EXISTS specification_rule;
+        DROP TABLE IF EXISTS specification_measurement;
+        DROP TABLE IF EXISTS response_option;
+        DROP TABLE IF EXISTS response_scale;
+        PRAGMA user_version = 0;
+        COMMIT;
+        "
+        )
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let count: u64 = conn.query_row("SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+                                    [table, column],
+                                    |row| row.get(0))?;
+    Ok(count > 0)
+}
+
+// ALTER TABLE has no *IF NOT EXISTS*, this keeps the step idempotent.
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))?;
+    }
+
+    Ok(())
+}
+
+// Version 1: job templates.
+fn create_templates(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS section (
+            id INTEGER PRIMARY KEY,
+            section_name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS job_type (
+            id INTEGER PRIMARY KEY,
+            job_type_name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS additive_section (
+            section_id INTEGER,
+            FOREIGN KEY (section_id)
+                REFERENCES section (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );
+        CREATE TABLE IF NOT EXISTS specification (
+            id INTEGER PRIMARY KEY,
+            specification_content TEXT NOT NULL UNIQUE,
+            section_id INTEGER,
+            FOREIGN KEY (section_id)
+                REFERENCES section (id)
+                    ON DELETE SET NULL
+                    ON UPDATE NO ACTION
+        );
+        CREATE TABLE IF NOT EXISTS job_specification (
+            job_type_id INTEGER,
+            specification_id INTEGER,
+            PRIMARY KEY (job_type_id, specification_id),
+            FOREIGN KEY (job_type_id)
+                REFERENCES job_type (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION,
+            FOREIGN KEY (specification_id)
+                REFERENCES specification (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 2: stored reports, and the template revision they were answered against.
+fn create_reports(conn: &Connection) -> Result<()> {
+    add_column(conn, "job_type", "template_revision", "INTEGER NOT NULL DEFAULT 1")?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report (
+            id INTEGER PRIMARY KEY,
+            job_type_id INTEGER,
+            job_name TEXT NOT NULL,
+            template_revision INTEGER,
+            FOREIGN KEY (job_type_id)
+                REFERENCES job_type (id)
+                    ON DELETE SET NULL
+                    ON UPDATE NO ACTION
+        );
+        CREATE TABLE IF NOT EXISTS report_header (
+            report_id INTEGER PRIMARY KEY,
+            engineer TEXT NOT NULL,
+            job TEXT NOT NULL,
+            day INTEGER NOT NULL,
+            month INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );
+        CREATE TABLE IF NOT EXISTS report_unit_form (
+            report_id INTEGER,
+            form_id INTEGER,
+            section_id INTEGER NOT NULL,
+            section_name TEXT NOT NULL,
+            specification_id INTEGER NOT NULL,
+            specification_content TEXT NOT NULL,
+            status TEXT NOT NULL,
+            notes TEXT NOT NULL,
+            PRIMARY KEY (report_id, form_id),
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 3: a section can only be additive once.
+fn unique_additive_sections(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DELETE FROM additive_section
+            WHERE section_id IS NULL
+            OR rowid NOT IN (SELECT MIN(rowid) FROM additive_section GROUP BY section_id);
+        CREATE UNIQUE INDEX IF NOT EXISTS additive_section_id ON additive_section (section_id);")
+}
+
+// Version 4: response scales, attached to a job type or overridden by a specification.
+fn create_response_scales(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS response_scale (
+            id INTEGER PRIMARY KEY,
+            scale_name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS response_option (
+            id INTEGER PRIMARY KEY,
+            scale_id INTEGER NOT NULL,
+            code TEXT NOT NULL,
+            label TEXT NOT NULL,
+            status TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            UNIQUE (scale_id, code),
+            FOREIGN KEY (scale_id)
+                REFERENCES response_scale (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")?;
+
+    add_column(conn, "job_type", "response_scale_id", "INTEGER REFERENCES response_scale (id) ON DELETE SET NULL")?;
+    add_column(conn, "specification", "response_scale_id", "INTEGER REFERENCES response_scale (id) ON DELETE SET NULL")?;
+    add_column(conn, "report_unit_form", "response_code", "TEXT")?;
+    add_column(conn, "report_unit_form", "response_label", "TEXT")
+}
+
+// Version 5: specifications answered with a measured value.
+fn create_measurements(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS specification_measurement (
+            specification_id INTEGER PRIMARY KEY,
+            unit TEXT NOT NULL,
+            nominal_value REAL NOT NULL,
+            min_value REAL,
+            max_value REAL,
+            FOREIGN KEY (specification_id)
+                REFERENCES specification (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")?;
+
+    add_column(conn, "report_unit_form", "value", "REAL")?;
+    add_column(conn, "report_unit_form", "measurement_unit", "TEXT")?;
+    add_column(conn, "report_unit_form", "nominal_value", "REAL")?;
+    add_column(conn, "report_unit_form", "min_value", "REAL")?;
+    add_column(conn, "report_unit_form", "max_value", "REAL")
+}
+
+// Version 6: weighted scoring, and the threshold a report has to reach to pass.
+fn create_scoring(conn: &Connection) -> Result<()> {
+    add_column(conn, "specification", "weight", "INTEGER NOT NULL DEFAULT 1")?;
+    add_column(conn, "job_type", "pass_threshold", "REAL")?;
+    add_column(conn, "report_unit_form", "weight", "INTEGER NOT NULL DEFAULT 1")?;
+    add_column(conn, "report", "score", "REAL")?;
+    add_column(conn, "report", "pass_threshold", "REAL")?;
+    add_column(conn, "report", "verdict", "TEXT")
+}
+
+// Version 7: skip logic, a specification or section shown or hidden depending on the status of another specification.
+fn create_specification_rules(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS specification_rule (
+            id INTEGER PRIMARY KEY,
+            target_specification_id INTEGER,
+            target_section_id INTEGER,
+            depends_on_specification_id INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            action TEXT NOT NULL,
+            CHECK ((target_specification_id IS NULL) != (target_section_id IS NULL)),
+            FOREIGN KEY (target_specification_id)
+                REFERENCES specification (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION,
+            FOREIGN KEY (target_section_id)
+                REFERENCES section (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION,
+            FOREIGN KEY (depends_on_specification_id)
+                REFERENCES specification (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 8: files attached to the forms of a report. The content lives in the attachment directory, keyed by hash.
+fn create_report_attachments(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report_attachment (
+            report_id INTEGER,
+            form_id INTEGER,
+            hash TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            media_type TEXT NOT NULL,
+            PRIMARY KEY (report_id, form_id, hash),
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 9: audit trail of the edits made to the forms of a report.
+fn create_report_history(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report_form_change (
+            id INTEGER PRIMARY KEY,
+            report_id INTEGER,
+            form_id INTEGER,
+            timestamp INTEGER NOT NULL,
+            engineer TEXT NOT NULL,
+            field TEXT NOT NULL,
+            old_value TEXT NOT NULL,
+            new_value TEXT NOT NULL,
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 10: review and sign-off of reports.
+fn add_report_review(conn: &Connection) -> Result<()> {
+    add_column(conn, "report", "review_state", "TEXT NOT NULL DEFAULT 'draft'")?;
+    add_column(conn, "report", "reviewer", "TEXT")?;
+    add_column(conn, "report", "review_comments", "TEXT NOT NULL DEFAULT ''")
+}
+
+// Version 11: response scale each form was answered with, copied like the specification text.
+fn create_report_response_scales(conn: &Connection) -> Result<()> {
+    add_column(conn, "report_unit_form", "scale_id", "INTEGER")?;
+    add_column(conn, "report_unit_form", "scale_name", "TEXT")?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report_form_option (
+            id INTEGER PRIMARY KEY,
+            report_id INTEGER,
+            form_id INTEGER,
+            code TEXT NOT NULL,
+            label TEXT NOT NULL,
+            status TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
+
+// Version 12: skip logic the report was answered with, so hidden forms can be shown again once the report is edited.
+fn create_report_rules(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS report_rule (
+            id INTEGER PRIMARY KEY,
+            report_id INTEGER,
+            target_specification_id INTEGER,
+            target_section_id INTEGER,
+            depends_on_specification_id INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            action TEXT NOT NULL,
+            CHECK ((target_specification_id IS NULL) != (target_section_id IS NULL)),
+            FOREIGN KEY (report_id)
+                REFERENCES report (id)
+                    ON DELETE CASCADE
+                    ON UPDATE NO ACTION
+        );")
+}
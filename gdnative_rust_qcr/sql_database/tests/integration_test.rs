@@ -1,66 +1,53 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{Connection, Result};
 use sql_database::db_handler::DBQualityControlHandle;
-use sql_database::db::{JobSpecificationSection, Section,JobType, Specification};
+use sql_database::error::{DbError, Entity, Key};
+use questionnaire::document::ReportHeader;
+use sql_database::db::{DBQualityControl, JobSpecificationSection, Section,JobType, Specification, Report, ReportRecord, ReportUnitForm, ResponseOption, ResponseScale, Measurement, ReportScore, SpecificationRule, ReportAttachment, ReportFormChange, ReportReview};
 
-const TEST_DATABASE_PATH: &'static str = "tests/database_test/";
+const TEST_DATABASE_PATH: &str = "tests/database_test/";
 
+// The schema is owned by the sql_database crate.
 fn create_test_tables(conn: &Connection) -> Result<()> {
+    DBQualityControl::initialize(conn)?;
+    Ok(())
+}
+
+fn drop_test_tables(conn: &Connection) -> Result<()> {
+    DBQualityControl::drop_schema(conn)
+}
+
+// Schema written by the CSV importer before the database was versioned.
+fn create_legacy_tables(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "BEGIN;
-        CREATE TABLE IF NOT EXISTS section (
+        CREATE TABLE section (
             id INTEGER PRIMARY KEY,
             section_name TEXT NOT NULL UNIQUE
         );
-        CREATE TABLE IF NOT EXISTS job_type (
+        CREATE TABLE job_type (
             id INTEGER PRIMARY KEY,
             job_type_name TEXT NOT NULL UNIQUE
         );
-        CREATE TABLE IF NOT EXISTS additive_section (
+        CREATE TABLE additive_section (
             section_id INTEGER,
-            FOREIGN KEY (section_id)
-                REFERENCES section (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION
+            FOREIGN KEY (section_id) REFERENCES section (id) ON DELETE CASCADE ON UPDATE NO ACTION
         );
-        CREATE TABLE IF NOT EXISTS specification (
+        CREATE TABLE specification (
             id INTEGER PRIMARY KEY,
             specification_content TEXT NOT NULL UNIQUE,
             section_id INTEGER,
-            FOREIGN KEY (section_id)
-                REFERENCES section (id)
-                    ON DELETE SET NULL
-                    ON UPDATE NO ACTION
+            FOREIGN KEY (section_id) REFERENCES section (id) ON DELETE SET NULL ON UPDATE NO ACTION
         );
-        CREATE TABLE IF NOT EXISTS job_specification (
+        CREATE TABLE job_specification (
             job_type_id INTEGER,
             specification_id INTEGER,
             PRIMARY KEY (job_type_id, specification_id),
-            FOREIGN KEY (job_type_id)
-                REFERENCES job_type (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION,
-            FOREIGN KEY (specification_id)
-                REFERENCES specification (id)
-                    ON DELETE CASCADE
-                    ON UPDATE NO ACTION
+            FOREIGN KEY (job_type_id) REFERENCES job_type (id) ON DELETE CASCADE ON UPDATE NO ACTION,
+            FOREIGN KEY (specification_id) REFERENCES specification (id) ON DELETE CASCADE ON UPDATE NO ACTION
         );
         COMMIT;")
 }
 
-fn drop_test_tables(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        "
-        BEGIN;
-        DROP TABLE IF EXISTS section;
-        DROP TABLE IF EXISTS additive_section;
-        DROP TABLE IF EXISTS job_type;
-        DROP TABLE IF EXISTS specification;
-        DROP TABLE IF EXISTS job_specification;
-        COMMIT;
-        "
-        )
-}
-
 fn populate_section(conn: &Connection) -> Result<usize> {
     let mut stmt = conn.prepare("INSERT INTO section (section_name) VALUES (?1)")?;
 
@@ -101,7 +88,7 @@ fn populate_specification(conn: &Connection) -> Result<usize> {
 }
 
 fn close_database(conn: Connection) -> Result<()> {
-    if let Err((conn,error)) = conn.close() {
+    if let Err((_,error)) = conn.close() {
         Err(error)
     } else {
         Ok(())
@@ -115,6 +102,8 @@ fn reset_tables(database_path: &String) -> Result<()> {
         close_database(conn)
 }
 
+// Used to inspect the section table while debugging.
+#[allow(dead_code)]
 fn all_sections(conn: &Connection) -> Result<Vec<String>> {
     let mut stmt = conn.prepare("SELECT * FROM section")?;
     let rows = stmt.query_map([], |row| row.get(1))?;
@@ -129,6 +118,7 @@ fn all_sections(conn: &Connection) -> Result<Vec<String>> {
 }
 
 fn setup_testing_env(database_path: &String) -> Result<()> {
+    std::fs::create_dir_all(TEST_DATABASE_PATH).unwrap();
     let conn = Connection::open(database_path)?;
 
     drop_test_tables(&conn)?;
@@ -138,26 +128,49 @@ fn setup_testing_env(database_path: &String) -> Result<()> {
 
 }
 
-fn show_errors(es: Vec<rusqlite::Error>) {
-    for error in es {
-        println!("{}",error);
-    }
+fn show_errors<E: std::fmt::Display>(e: E) {
+    println!("{}", e);
 }
 
-fn fail_test(es: Vec<rusqlite::Error>, database_path: &String) {
-    show_errors(es);
-    reset_tables(database_path);
-    assert!(false);
+fn fail_test<E: std::fmt::Display>(e: E, database_path: &String) {
+    show_errors(e);
+    reset_tables(database_path).unwrap();
+    panic!();
 
 }
 
 fn setup_data(db: &mut DBQualityControlHandle) {
-        db.add_job_type("Cement".to_string());
-        db.add_job_type("Horizontal".to_string());
-        db.add_section("Cover Page".to_string());
-        db.add_section("Well Data".to_string());
-        db.add_specification("Title".to_string(), Some(1));
-        db.add_specification("Subtitle".to_string(), Some(1));
+        db.add_job_type("Cement".to_string()).unwrap();
+        db.add_job_type("Horizontal".to_string()).unwrap();
+        db.add_section("Cover Page".to_string()).unwrap();
+        db.add_section("Well Data".to_string()).unwrap();
+        db.add_specification("Title".to_string(), Some(1)).unwrap();
+        db.add_specification("Subtitle".to_string(), Some(1)).unwrap();
+}
+
+fn report_header() -> ReportHeader {
+    ReportHeader::new("Engineer".to_string(), "Well 7".to_string(), 1, 2, 2023)
+}
+
+// Report without rules, score or history.
+fn save_plain_report(db: &mut DBQualityControlHandle, job_type_id: Option<u64>, forms: &[ReportUnitForm]) -> Result<u64, DbError> {
+    let record = ReportRecord { header: &report_header(), forms, rules: &[], score: None, history: &[], review: &ReportReview::default() };
+    db.save_report(job_type_id, &record)
+}
+
+fn report_forms() -> Vec<ReportUnitForm> {
+    vec![
+        ReportUnitForm::new(0, 1, "Cover Page".to_string(), 1, "Title".to_string(), "OK".to_string(), "".to_string()),
+        ReportUnitForm::new(1, 1, "Cover Page".to_string(), 2, "Subtitle".to_string(), "NO".to_string(), "Subtitle missing".to_string())
+            .with_response("MAJ".to_string(), "Major".to_string())
+            .with_attachments(vec![
+                ReportAttachment::new("ab12".to_string(), "cover.jpg".to_string(), "image/jpeg".to_string()),
+                ReportAttachment::new("cd34".to_string(), "log.txt".to_string(), "text/plain".to_string()),
+            ]),
+        ReportUnitForm::new(2, 2, "Well Data".to_string(), 3, "Slurry density".to_string(), "OK".to_string(), "".to_string())
+            .with_measurement(Measurement::new("ppg".to_string(), 15.8, Some(15.6), Some(16.0)), Some(15.9))
+            .with_weight(3),
+    ]
 }
 
 #[cfg(test)]
@@ -165,25 +178,24 @@ mod tests {
     use super::*;
     #[test]
     fn table_creation() {
-        let conn = Connection::open("tests/database_test/testing_db.db".to_owned());
+        std::fs::create_dir_all(TEST_DATABASE_PATH).unwrap();
+        let conn = Connection::open("tests/database_test/testing_db.db");
         if let Err(e) = &conn {
             println!("ERROR opening database: {}",e);
-            assert!(false);
+            panic!();
         }
 
         let conn = conn.unwrap();
         if let Err(e) = create_test_tables(&conn) {
             println!("ERROR creating tables: {}",e);
-            drop_test_tables(&conn);
-            Connection::close(conn);
+            drop_test_tables(&conn).unwrap();
+            Connection::close(conn).unwrap();
 
-            assert!(false);
+            panic!();
         } else {
-            drop_test_tables(&conn);
-            Connection::close(conn);
+            drop_test_tables(&conn).unwrap();
+            Connection::close(conn).unwrap();
         }
-
-        assert!(true);
     }
 
     #[test]
@@ -193,32 +205,32 @@ mod tests {
         let conn = Connection::open("tests/testing_db.db");
         if let Err(e) = &conn {
             println!("ERROR opening database: {}",e);
-            assert!(false);
+            panic!();
         }
 
         let conn = conn.unwrap();
-        drop_test_tables(&conn);
+        drop_test_tables(&conn).unwrap();
 
         if let Err(e) = create_test_tables(&conn) {
             println!("ERROR creating tables: {}",e);
-            assert!(false);
+            panic!();
         }
 
         if let Err(e) = populate_section(&conn) {
             println!("ERROR populating sections: {}",e);
-            assert!(false);
+            panic!();
         }
         if let Err(e) = populate_job_type(&conn) {
             println!("ERROR populating job types: {}",e);
-            assert!(false);
+            panic!();
         }
         if let Err(e) = populate_specification(&conn) {
             println!("ERROR populating specification: {}",e);
-            assert!(false);
+            panic!();
         }
 
-        drop_test_tables(&conn);
-        Connection::close(conn);
+        drop_test_tables(&conn).unwrap();
+        Connection::close(conn).unwrap();
     }
 
     #[test]
@@ -230,7 +242,7 @@ mod tests {
 
         // Prepare tables
         if let Err(e) = setup_testing_env(&db.database_path) {
-            fail_test(vec![e], &db.database_path);
+            fail_test(e, &db.database_path);
         }
 
         // Insert the section
@@ -254,7 +266,7 @@ mod tests {
             println!("{}",v);
         }*/
 
-        reset_tables(&db.database_path);
+        reset_tables(&db.database_path).unwrap();
 
 
         // Verify values found in row.
@@ -265,7 +277,7 @@ mod tests {
             },
             Err(e) => {
                 println!("ERROR: Failed to add section: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
@@ -276,7 +288,7 @@ mod tests {
         let mut db = DBQualityControlHandle::new("tests/testing_db_job_type.db".to_string());
 
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
 
         // Insert the section
         if let Err(e) = db.add_job_type("Cement".to_string()) {
@@ -309,13 +321,13 @@ mod tests {
             },
             Err(e) => {
                 println!("ERROR: Failed to add cement: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
 
-        drop_test_tables(&conn);
-        close_database(conn);
+        drop_test_tables(&conn).unwrap();
+        close_database(conn).unwrap();
     }
 
     #[test]
@@ -326,15 +338,15 @@ mod tests {
         let conn = conn.unwrap();
 
         // Prepare tables
-        drop_test_tables(&conn);
-        create_test_tables(&conn);
+        drop_test_tables(&conn).unwrap();
+        create_test_tables(&conn).unwrap();
 
         // Insert the section
-        db.add_section("Cover Page".to_string());
+        db.add_section("Cover Page".to_string()).unwrap();
         //
         if let Err(e) = db.add_specification("Title".to_string(), Some(1)) {
             show_errors(e);
-            assert!(false);
+            panic!();
         }
 
         // Check the row was successful.
@@ -364,13 +376,13 @@ mod tests {
             },
             Err(e) => {
                 println!("ERROR: Failed to add Title specification: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
 
-        drop_test_tables(&conn);
-        close_database(conn);
+        drop_test_tables(&conn).unwrap();
+        close_database(conn).unwrap();
     }
 
     #[test]
@@ -381,15 +393,15 @@ mod tests {
         let conn = conn.unwrap();
 
         // Prepare tables
-        drop_test_tables(&conn);
-        create_test_tables(&conn);
+        drop_test_tables(&conn).unwrap();
+        create_test_tables(&conn).unwrap();
 
         // Insert the section
-        db.add_section("Cover Page".to_string());
+        db.add_section("Cover Page".to_string()).unwrap();
         //
         if let Err(e) = db.add_specification("Title".to_string(), None) {
             show_errors(e);
-            assert!(false);
+            panic!();
         }
 
         // Check the row was successful.
@@ -417,18 +429,17 @@ mod tests {
             Ok((id, spec,Ok(None))) => {
                 assert_eq!(1, id);
                 assert_eq!("Title", spec);
-                assert!(true);
             },
-            Ok(_) => assert!(false),
+            Ok(_) => panic!(),
             Err(e) => {
                 println!("ERROR: Failed to add Title specification: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
 
-        drop_test_tables(&conn);
-        close_database(conn);
+        drop_test_tables(&conn).unwrap();
+        close_database(conn).unwrap();
     }
 
 
@@ -440,29 +451,23 @@ mod tests {
         let conn = conn.unwrap();
 
         // Prepare tables
-        drop_test_tables(&conn);
-        create_test_tables(&conn);
+        drop_test_tables(&conn).unwrap();
+        create_test_tables(&conn).unwrap();
 
         // Insert the section
-        db.add_section("Cover Page".to_string());
+        db.add_section("Cover Page".to_string()).unwrap();
         //
         if let Err(e) = db.add_additive_section("Cover Page".to_string()) {
             show_errors(e);
-            assert!(false);
+            panic!();
         }
 
         // Check the row was successful.
         let id_name_op = conn.query_row(
             "SELECT section_id FROM additive_section WHERE section_id = ?1",
             [1],
-            |row| {
-                let id = row.get::<usize,u64>(0)?;
-                
-                // Value can be null or not.
-                let section_id  = row.get(0);
-
-                section_id
-            });
+            // Value can be null or not.
+            |row| row.get::<_, i32>(0));
 
         // Show all names in section
         /* for v in all_sections(&conn).unwrap() {
@@ -474,17 +479,16 @@ mod tests {
         match id_name_op {
             Ok(id) => {
                 assert_eq!(1, id);
-                assert!(true);
             },
             Err(e) => {
                 println!("ERROR: Failed to add Title specification: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
 
-        drop_test_tables(&conn);
-        close_database(conn);
+        drop_test_tables(&conn).unwrap();
+        close_database(conn).unwrap();
     }
 
     #[test]
@@ -495,30 +499,30 @@ mod tests {
         let conn = conn.unwrap();
 
         // Prepare tables
-        drop_test_tables(&conn);
-        create_test_tables(&conn);
+        drop_test_tables(&conn).unwrap();
+        create_test_tables(&conn).unwrap();
 
         // Insert the section
         if let Err(e) = db.add_section("Cover Page".to_string()) {
             println!("ERROR in Job specification");
             show_errors(e);
-                assert!(false);
+                panic!();
         }
         if let Err(e) = db.add_job_type("Cement".to_string()) {
             println!("ERROR in adding a job type");
             show_errors(e);
-                assert!(false);
+                panic!();
         }
         if let Err(e) = db.add_specification("Title".to_string(), None) {
             println!("ERROR in adding a specification:");
             show_errors(e);
-                assert!(false);
+                panic!();
         }
 
         if let Err(e) = db.add_job_specification(1,1) {
             println!("ERROR in adding a job specification:");
             show_errors(e);
-            assert!(false);
+            panic!();
         }
 
         // Check the row was successful.
@@ -545,39 +549,38 @@ mod tests {
             Ok((job_type_id, spec_id)) => {
                 assert_eq!(1, job_type_id);
                 assert_eq!(1, spec_id);
-                assert!(true);
             },
             Err(e) => {
                 println!("ERROR: Failed to add Title specification: {}",e);
-                assert!(false);
+                panic!();
             }
         }
 
 
-        drop_test_tables(&conn);
-        close_database(conn);
+        drop_test_tables(&conn).unwrap();
+        close_database(conn).unwrap();
     }
 
     #[test]
     fn update_section() {
         let mut db = DBQualityControlHandle::new("test_db_update_section.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
 
-        db.add_section("Cover Page".to_string());
+        db.add_section("Cover Page".to_string()).unwrap();
 
         let section_update = String::from("Cover Page - Header");
 
         /*
         if let Err((conn, errors)) = db.update_section(1, section_update) {
             show_errors(errors);
-            assert!(false);
+            panic!();
         }*/
 
         if let Err(e) = db.update_section(1,section_update) {
             println!("ERROR updating section");
             show_errors(e);
-            assert!(false);
+            panic!();
         }
         let result = db.get_section(1);
         /*
@@ -594,7 +597,7 @@ mod tests {
         if let Ok(section) = result {
             assert_eq!("Cover Page - Header".to_string(), section.section_name);
         } else {
-            assert!(false);
+            panic!();
         }
 
     }
@@ -603,18 +606,18 @@ mod tests {
     fn update_job_type() {
         let mut db = DBQualityControlHandle::new("test_db_update_job_type.db".to_string());
 
-        setup_testing_env(&db.database_path);
-        db.add_job_type("Cement".to_string());
+        setup_testing_env(&db.database_path).unwrap();
+        db.add_job_type("Cement".to_string()).unwrap();
 
         let job_type_update = String::from("Cement Elongation");
-        db.update_job_type(1,job_type_update);
+        db.update_job_type(1,job_type_update).unwrap();
 
         let result = db.get_job_type(1);
 
         /*
         if let Err((conn, errors)) = db.update_job_type(1, job_type_update) {
             show_errors(errors);
-            assert!(false);
+            panic!();
         }
         */
 
@@ -623,7 +626,7 @@ mod tests {
         if let Ok(job_type_name) = result {
             assert_eq!("Cement Elongation".to_string(), job_type_name.job_type_name);
         } else {
-            assert!(false);
+            panic!();
         }
     }
 
@@ -631,14 +634,14 @@ mod tests {
     fn update_specification_content() {
         let mut db = DBQualityControlHandle::new("test_db_update_specification_content.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
 
         // Prepare tables
-        db.add_job_type("Cement".to_string());
-        db.add_section("Cover Page".to_string());
-        db.add_specification("Title".to_string(), Some(1));
+        db.add_job_type("Cement".to_string()).unwrap();
+        db.add_section("Cover Page".to_string()).unwrap();
+        db.add_specification("Title".to_string(), Some(1)).unwrap();
 
-        db.update_specification_content(1,"Title - Header".to_string());
+        db.update_specification_content(1,"Title - Header".to_string()).unwrap();
 
         let result = db.get_specification(1);
 
@@ -648,7 +651,7 @@ mod tests {
             },
             Err(e) => {
                 show_errors(e);
-                assert!(false);
+                panic!();
             }
         }
 
@@ -658,14 +661,14 @@ mod tests {
     fn update_specification_section() {
         let mut db = DBQualityControlHandle::new("test_db_update_specification_section.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
 
-        db.add_job_type("Cement".to_string());
-        db.add_section("Cover Page".to_string());
-        db.add_section("Well Data".to_string());
-        db.add_specification("Title".to_string(), Some(1));
+        db.add_job_type("Cement".to_string()).unwrap();
+        db.add_section("Cover Page".to_string()).unwrap();
+        db.add_section("Well Data".to_string()).unwrap();
+        db.add_specification("Title".to_string(), Some(1)).unwrap();
 
-        db.update_specification_section(1,2);
+        db.update_specification_section(1,2).unwrap();
 
         let result = db.get_specification(1);
 
@@ -674,10 +677,10 @@ mod tests {
                 Some(s) => {
                     assert_eq!(2, s.id);
                 },
-                _ => {assert!(false);}
+                _ => {panic!();}
             }
         } else {
-            assert!(false);
+            panic!();
         }
     }
 
@@ -685,65 +688,53 @@ mod tests {
     fn remove_specification() {
         let mut db = DBQualityControlHandle::new("tests/database_test/test_db_remove_specification.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
-        db.remove_specification(1);
+        db.remove_specification(1).unwrap();
 
         let result = db.get_specification(1);
 
-        if let Err(_) = result {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(result.is_err());
     }
 
     #[test]
     fn remove_section() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_remove_section.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
-        db.remove_section(1);
+        db.remove_section(1).unwrap();
 
         let result = db.get_section(1);
 
-        if let Err(_) = result {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(result.is_err());
     }
 
     #[test]
     fn remove_job_type() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_remove_job_type.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
-        db.remove_job_type(1);
+        db.remove_job_type(1).unwrap();
 
         let result = db.get_job_type(1);
 
-        if let Err(_) = result {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(result.is_err());
     }
 
     #[test]
     fn all_job_specification() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_all_job_specification.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
-        db.add_job_specification(1,1);
-        db.add_job_specification(1,2);
+        db.add_job_specification(1,1).unwrap();
+        db.add_job_specification(1,2).unwrap();
 
         let result = db.get_all_job_specification(1);
 
@@ -755,7 +746,7 @@ mod tests {
         match result {
             Ok(job_spec) => {
                 if job_spec.len() != 2 {
-                    assert!(false);
+                    panic!();
                 } else {
                     let q_0 = &job_spec[0];
                     let q_1 = &job_spec[1];
@@ -765,7 +756,7 @@ mod tests {
                     
                 }
             },
-            Err(e) => assert!(false),
+            Err(e) => panic!("{}", e),
         }
         
     }
@@ -774,7 +765,7 @@ mod tests {
     fn get_all_job_types() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_all_job_types.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
         let result = db.get_all_job_types();
@@ -790,7 +781,7 @@ mod tests {
             },
             Err(e) => {
                 show_errors(e);
-                assert!(false);
+                panic!();
             }
         }
     }
@@ -799,7 +790,7 @@ mod tests {
     fn get_all_sections() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_all_sections.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
         let result = db.get_all_sections();
@@ -815,7 +806,7 @@ mod tests {
             },
             Err(e) => {
                 show_errors(e);
-                assert!(false);
+                panic!();
             }
         }
     }
@@ -824,7 +815,7 @@ mod tests {
     fn get_all_specifications() {
         let mut db = DBQualityControlHandle::new("tests/database_test/db_all_specs.db".to_string());
 
-        setup_testing_env(&db.database_path);
+        setup_testing_env(&db.database_path).unwrap();
         setup_data(&mut db);
 
         let result = db.get_all_specifications();
@@ -842,11 +833,610 @@ mod tests {
             },
             Err(e) => {
                 show_errors(e);
-                assert!(false);
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn add_get_report() {
+        let mut db = DBQualityControlHandle::new("tests/testing_db_add_report.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let report_id = match save_plain_report(&mut db, Some(1), &report_forms()) {
+            Ok(id) => id,
+            Err(e) => {
+                fail_test(e, &db.database_path);
+                return;
+            }
+        };
+
+        let expected_report = Report {
+            id: 1,
+            job_type_id: Some(1),
+            job_name: "Cement".to_string(),
+            template_revision: Some(1),
+            header: report_header(),
+            score: None,
+            review: ReportReview::default(),
+            forms: report_forms(),
+            rules: Vec::new(),
+        };
+
+        match db.get_report(report_id) {
+            Ok(report) => {
+                assert_eq!(expected_report, report);
+            },
+            Err(e) => {
+                show_errors(e);
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn get_all_reports() {
+        let mut db = DBQualityControlHandle::new("tests/testing_db_all_reports.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+        save_plain_report(&mut db, None, &[]).unwrap();
+
+        match db.get_all_reports() {
+            Ok(reports) => {
+                assert_eq!(2, reports.len());
+                assert_eq!(Some(1), reports[0].job_type_id);
+                assert_eq!(None, reports[1].job_type_id);
+                assert_eq!(report_header(), reports[1].header);
+            },
+            Err(e) => {
+                show_errors(e);
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn remove_report() {
+        let mut db = DBQualityControlHandle::new("tests/testing_db_remove_report.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+        db.remove_report(1).unwrap();
+
+        assert!(db.get_report(1).is_err());
+
+        // Forms are removed alongside the report.
+        let conn = Connection::open(&db.database_path).unwrap();
+        let form_count: u64 = conn.query_row("SELECT COUNT(*) FROM report_unit_form", [], |row| row.get(0)).unwrap();
+
+        assert_eq!(0, form_count);
+    }
+
+    #[test]
+    fn report_keeps_template_snapshot() {
+        let mut db = DBQualityControlHandle::new("tests/testing_db_report_snapshot.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        db.add_job_specification(1,1).unwrap();
+        db.add_job_specification(1,2).unwrap();
+
+        let revision = db.get_job_template_revision(1).unwrap();
+        save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+
+        // Edit the template after the report was stored.
+        db.update_section(1, "Cover Page - Header".to_string()).unwrap();
+        db.update_specification_content(1, "Title - Header".to_string()).unwrap();
+
+        match db.get_report(1) {
+            Ok(report) => {
+                assert_eq!(Some(revision), report.template_revision);
+                assert_eq!("Cover Page", report.forms[0].section_name);
+                assert_eq!("Title", report.forms[0].specification_content);
+            },
+            Err(e) => {
+                show_errors(e);
+                panic!();
+            }
+        }
+
+        // Both edits touched the job template.
+        assert_eq!(revision + 2, db.get_job_template_revision(1).unwrap());
+        // Job type 2 does not use any of the edited specifications.
+        assert_eq!(1, db.get_job_template_revision(2).unwrap());
+    }
+
+    #[test]
+    fn migrate_legacy_database() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_migrate_legacy.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+
+        // Start from an unversioned database that already holds templates.
+        let conn = Connection::open(&db.database_path).unwrap();
+        drop_test_tables(&conn).unwrap();
+        create_legacy_tables(&conn).unwrap();
+        populate_section(&conn).unwrap();
+        populate_job_type(&conn).unwrap();
+        populate_specification(&conn).unwrap();
+        close_database(conn).unwrap();
+
+        match db.initialize() {
+            Ok(version) => assert_eq!(sql_database::migrations::LATEST_VERSION, version),
+            Err(e) => {
+                fail_test(e, &db.database_path);
+                return;
+            }
+        }
+
+        // Existing data is kept, and the new tables can be used.
+        assert_eq!(JobType::new(1, "Cement".to_string()), db.get_job_type(1).unwrap());
+        assert_eq!(1, db.get_job_template_revision(1).unwrap());
+        assert_eq!(12, db.get_all_specifications().unwrap().len());
+
+        if let Err(e) = save_plain_report(&mut db, Some(1), &report_forms()) {
+            fail_test(e, &db.database_path);
+        }
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_migrate_twice.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let first = db.initialize().unwrap();
+        let second = db.initialize().unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(2, db.get_all_sections().unwrap().len());
+    }
+
+    #[test]
+    fn migrate_to_version() {
+        std::fs::create_dir_all(TEST_DATABASE_PATH).unwrap();
+        let conn = Connection::open("tests/database_test/db_migrate_version.db").unwrap();
+        drop_test_tables(&conn).unwrap();
+
+        assert_eq!(1, DBQualityControl::migrate(&conn, 1).unwrap());
+
+        // Reports only exist from version 2.
+        assert!(conn.prepare("SELECT id FROM report").is_err());
+
+        assert_eq!(2, DBQualityControl::migrate(&conn, 2).unwrap());
+        assert!(conn.prepare("SELECT id FROM report").is_ok());
+    }
+
+    #[test]
+    fn additive_sections() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_additive_sections.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        db.add_additive_section("Well Data".to_string()).unwrap();
+
+        // A section can only be additive once.
+        assert!(db.add_additive_section("Well Data".to_string()).is_err());
+        assert_eq!(vec![Section::new(2, "Well Data".to_string())], db.get_all_additive_sections().unwrap());
+
+        db.remove_additive_section(2).unwrap();
+
+        assert_eq!(0, db.get_all_additive_sections().unwrap().len());
+    }
+
+    #[test]
+    fn section_specifications() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_section_specifications.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+        db.add_specification("Depth".to_string(), Some(2)).unwrap();
+
+        let section_one = Section::new(1, "Cover Page".to_string());
+        let expected_specs = vec![
+            Specification::new(1, "Title".to_string(), Some(section_one.clone())),
+            Specification::new(2, "Subtitle".to_string(), Some(section_one.clone())),
+        ];
+
+        match db.get_section_specifications(1) {
+            Ok(specs) => assert_eq!(expected_specs, specs),
+            Err(e) => {
+                show_errors(e);
+                panic!();
+            }
+        }
+    }
+
+    #[test]
+    fn orphaned_specifications() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_orphaned_specifications.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+        db.add_specification("Permit".to_string(), None).unwrap();
+
+        db.add_job_specification(1,1).unwrap();
+        db.add_job_specification(1,3).unwrap();
+
+        let orphan = Specification::new(3, "Permit".to_string(), None);
+
+        assert_eq!(orphan, db.get_specification(3).unwrap());
+
+        db.remove_specification_section(1).unwrap();
+        assert_eq!(None, db.get_specification(1).unwrap().section);
+        assert_eq!(3, db.get_all_specifications().unwrap().len());
+
+        let expected_job_specs = vec![
+            JobSpecificationSection::new(1,"Cement".to_string(),None,1,"Title".to_string()),
+            JobSpecificationSection::new(1,"Cement".to_string(),None,3,"Permit".to_string()),
+        ];
+
+        match db.get_all_job_specification(1) {
+            Ok(job_specs) => assert_eq!(expected_job_specs, job_specs),
+            Err(e) => {
+                show_errors(e);
+                panic!();
             }
         }
     }
 
+    #[test]
+    fn response_scales() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_response_scales.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        db.add_job_specification(1,2).unwrap();
+
+        db.add_response_scale("Severity".to_string()).unwrap();
+        db.add_response_option(1, "P".to_string(), "Pass".to_string(), "OK".to_string()).unwrap();
+        db.add_response_option(1, "MAJ".to_string(), "Major".to_string(), "NO".to_string()).unwrap();
+
+        // Codes are unique within a scale.
+        assert!(db.add_response_option(1, "P".to_string(), "Pass again".to_string(), "OK".to_string()).is_err());
+
+        let expected_scale = ResponseScale {
+            id: 1,
+            scale_name: "Severity".to_string(),
+            options: vec![
+                ResponseOption { id: 1, code: "P".to_string(), label: "Pass".to_string(), status: "OK".to_string(), position: 1 },
+                ResponseOption { id: 2, code: "MAJ".to_string(), label: "Major".to_string(), status: "NO".to_string(), position: 2 },
+            ]
+        };
+
+        assert_eq!(expected_scale, db.get_response_scale(1).unwrap());
+        assert_eq!(vec![expected_scale], db.get_all_response_scales().unwrap());
+
+        assert_eq!(None, db.get_job_type_response_scale(1).unwrap());
+        db.set_job_type_response_scale(1, Some(1)).unwrap();
+        db.set_specification_response_scale(2, Some(1)).unwrap();
+
+        assert_eq!(Some(1), db.get_job_type_response_scale(1).unwrap());
+        assert_eq!(vec![(2, 1)], db.get_all_specification_response_scales().unwrap());
+        // Both changes count as a new revision of the template.
+        assert_eq!(4, db.get_job_template_revision(1).unwrap());
+
+        // Removing the scale falls back to the default one.
+        db.remove_response_scale(1).unwrap();
+
+        assert_eq!(None, db.get_job_type_response_scale(1).unwrap());
+        assert_eq!(0, db.get_all_specification_response_scales().unwrap().len());
+        assert_eq!(0, db.get_all_response_scales().unwrap().len());
+    }
+
+    #[test]
+    fn specification_measurements() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_specification_measurements.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+        db.add_job_specification(1,1).unwrap();
+
+        let density = Measurement::new("ppg".to_string(), 15.8, Some(15.6), Some(16.0));
+
+        assert_eq!(None, db.get_specification_measurement(1).unwrap());
+
+        db.set_specification_measurement(1, Some(&density)).unwrap();
+        assert_eq!(Some(density.clone()), db.get_specification_measurement(1).unwrap());
+
+        // Replaces the previous limits.
+        let open_limit = Measurement::new("ppg".to_string(), 15.8, Some(15.6), None);
+        db.set_specification_measurement(1, Some(&open_limit)).unwrap();
+        assert_eq!(vec![(1, open_limit)], db.get_all_specification_measurements().unwrap());
+        assert_eq!(4, db.get_job_template_revision(1).unwrap());
+
+        db.set_specification_measurement(1, None).unwrap();
+        assert_eq!(0, db.get_all_specification_measurements().unwrap().len());
+    }
+
+    #[test]
+    fn report_scoring() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_report_scoring.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+        db.add_job_specification(1,1).unwrap();
+
+        db.set_specification_weight(1, 5).unwrap();
+        assert_eq!(vec![(1, 5)], db.get_all_specification_weights().unwrap());
+
+        assert_eq!(None, db.get_job_type_pass_threshold(1).unwrap());
+        db.set_job_type_pass_threshold(1, Some(80.0)).unwrap();
+        assert_eq!(Some(80.0), db.get_job_type_pass_threshold(1).unwrap());
+        assert_eq!(4, db.get_job_template_revision(1).unwrap());
+
+        let report_id = save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+        let score = ReportScore::new(75.0, Some(80.0), Some("FAIL".to_string()));
+        db.set_report_score(report_id, &score).unwrap();
+
+        assert_eq!(Some(score.clone()), db.get_report(report_id).unwrap().score);
+        assert_eq!(Some(score), db.get_all_reports().unwrap()[0].score);
+    }
+
+    #[test]
+    fn specification_rules() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_specification_rules.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let show_subtitle = SpecificationRule {
+            id: 1,
+            target_specification_id: Some(2),
+            target_section_id: None,
+            depends_on_specification_id: 1,
+            status: "NO".to_string(),
+            action: "show_if".to_string(),
+        };
+        let hide_well_data = SpecificationRule {
+            id: 2,
+            target_specification_id: None,
+            target_section_id: Some(2),
+            depends_on_specification_id: 1,
+            status: "N/A".to_string(),
+            action: "hide_if".to_string(),
+        };
+
+        db.add_specification_rule(&show_subtitle).unwrap();
+        db.add_specification_rule(&hide_well_data).unwrap();
+
+        // A rule targets either a specification or a section.
+        let both = SpecificationRule { target_section_id: Some(1), ..show_subtitle.clone() };
+        assert!(db.add_specification_rule(&both).is_err());
+
+        assert_eq!(vec![show_subtitle.clone(), hide_well_data], db.get_all_specification_rules().unwrap());
+
+        db.remove_specification_rule(2).unwrap();
+        assert_eq!(vec![show_subtitle], db.get_all_specification_rules().unwrap());
+
+        // Rules go away with the specification they depend on.
+        db.remove_specification(1).unwrap();
+        assert_eq!(0, db.get_all_specification_rules().unwrap().len());
+    }
+
+    #[test]
+    fn report_attachments() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_report_attachments.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let first = save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+        save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+
+        assert_eq!(vec!["ab12".to_string(), "cd34".to_string()], db.get_all_attachment_hashes().unwrap());
+
+        let report = db.get_report(first).unwrap();
+        assert_eq!(2, report.forms[1].attachments.len());
+        assert_eq!(0, report.forms[0].attachments.len());
+
+        db.remove_report(first).unwrap();
+        db.remove_report(2).unwrap();
+        assert_eq!(0, db.get_all_attachment_hashes().unwrap().len());
+    }
+
+    #[test]
+    fn report_response_scale() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_report_response_scale.db".to_string());
 
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
 
+        let options = vec![
+            ResponseOption { id: 0, code: "P".to_string(), label: "Pass".to_string(), status: "OK".to_string(), position: 1 },
+            ResponseOption { id: 0, code: "MAJ".to_string(), label: "Major".to_string(), status: "NO".to_string(), position: 2 },
+        ];
+        let mut forms = report_forms();
+        forms[1] = forms[1].clone().with_scale(ResponseScale { id: 4, scale_name: "Severity".to_string(), options });
+
+        let report_id = save_plain_report(&mut db, Some(1), &forms).unwrap();
+
+        // Only the forms stored with a scale have one.
+        let report = db.get_report(report_id).unwrap();
+        assert_eq!(None, report.forms[0].scale);
+
+        let scale = report.forms[1].scale.as_ref().unwrap();
+        assert_eq!((4, "Severity"), (scale.id, scale.scale_name.as_str()));
+        assert_eq!(vec!["P", "MAJ"], scale.options.iter().map(|option| option.code.as_str()).collect::<Vec<_>>());
+        assert_eq!(("Major", "NO", 2), (scale.options[1].label.as_str(), scale.options[1].status.as_str(), scale.options[1].position));
+    }
+
+    #[test]
+    fn report_history() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_report_history.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let report_id = save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+
+        let history = vec![
+            ReportFormChange::new(1, 1700000000, "J. Doe".to_string(), "answer".to_string(), "".to_string(), "NO".to_string()),
+            ReportFormChange::new(1, 1700000060, "J. Doe".to_string(), "notes".to_string(), "".to_string(), "Subtitle missing".to_string()),
+        ];
+
+        assert_eq!(2, db.add_report_history(report_id, &history).unwrap());
+        assert_eq!(history, db.get_report_history(report_id).unwrap());
+
+        db.remove_report(report_id).unwrap();
+        assert_eq!(0, db.get_report_history(report_id).unwrap().len());
+    }
+
+    #[test]
+    fn report_review() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_report_review.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let report_id = save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+        assert_eq!(ReportReview::default(), db.get_report(report_id).unwrap().review);
+
+        let review = ReportReview::new("returned".to_string(), Some("A. Smith".to_string()), "Missing photos".to_string());
+        db.set_report_review(report_id, &review).unwrap();
+
+        assert_eq!(review, db.get_report(report_id).unwrap().review);
+        assert_eq!(review, db.get_all_reports().unwrap()[0].review);
+    }
+
+    #[test]
+    fn save_report() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_save_report.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let score = ReportScore::new(75.0, Some(80.0), Some("FAIL".to_string()));
+        let history = vec![ReportFormChange::new(1, 1700000000, "J. Doe".to_string(), "answer".to_string(), "".to_string(), "NO".to_string())];
+        let review = ReportReview::new("submitted".to_string(), None, "".to_string());
+        let rule = SpecificationRule {
+            id: 1,
+            target_specification_id: Some(2),
+            target_section_id: None,
+            depends_on_specification_id: 1,
+            status: "NO".to_string(),
+            action: "show_if".to_string(),
+        };
+
+        let (header, forms, rules) = (report_header(), report_forms(), vec![rule.clone()]);
+        let record = ReportRecord { header: &header, forms: &forms, rules: &rules, score: Some(&score), history: &history, review: &review };
+        let report_id = db.save_report(Some(1), &record).unwrap();
+
+        // The rules are a copy, removing the ones of the template leaves the report as it was.
+        db.remove_specification(2).unwrap();
+
+        let report = db.get_report(report_id).unwrap();
+        assert_eq!(vec![rule], report.rules);
+        assert_eq!(Some(score.clone()), report.score);
+        assert_eq!(review, report.review);
+        assert_eq!(history, db.get_report_history(report_id).unwrap());
+
+        // A failed step leaves no partial report behind.
+        let conn = Connection::open(&db.database_path).unwrap();
+        conn.execute("DROP TABLE report_form_change", []).unwrap();
+        close_database(conn).unwrap();
+
+        assert!(db.save_report(Some(1), &record).is_err());
+        assert_eq!(1, db.get_all_reports().unwrap().len());
+    }
+
+    #[test]
+    fn update_report() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_update_report.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        let report_id = save_plain_report(&mut db, Some(1), &report_forms()).unwrap();
+
+        let mut forms = report_forms();
+        forms.truncate(1);
+        let score = ReportScore::new(100.0, None, None);
+        let history = vec![ReportFormChange::new(1, 1700000000, "J. Doe".to_string(), "answer".to_string(), "NO".to_string(), "OK".to_string())];
+        let review = ReportReview::new("submitted".to_string(), None, "".to_string());
+
+        let header = report_header();
+        let record = ReportRecord { header: &header, forms: &forms, rules: &[], score: Some(&score), history: &history, review: &review };
+        assert_eq!(1, db.update_report(report_id, &record).unwrap());
+
+        // Still a single report, with the new content only.
+        assert_eq!(1, db.get_all_reports().unwrap().len());
+        let report = db.get_report(report_id).unwrap();
+        assert_eq!(forms, report.forms);
+        assert_eq!(Some(score.clone()), report.score);
+        assert_eq!(review, report.review);
+        assert_eq!(history, db.get_report_history(report_id).unwrap());
+
+        assert!(db.update_report(99, &record).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn typed_errors() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_typed_errors.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+
+        match db.add_job_type("Cement".to_string()) {
+            Err(DbError::Duplicate { entity: Entity::JobType, key }) => assert_eq!(Key::Name("Cement".to_string()), key),
+            other => panic!("Expected a duplicate job type, got {:?}", other),
+        }
+        assert_eq!("A job type named \"Cement\" already exists", db.add_job_type("Cement".to_string()).unwrap_err().to_string());
+
+        match db.get_section(99) {
+            Err(DbError::NotFound { entity: Entity::Section, key }) => assert_eq!(Key::Id(99), key),
+            other => panic!("Expected a missing section, got {:?}", other),
+        }
+
+        // Nothing to update or remove.
+        match db.update_section(99, "Annex".to_string()) {
+            Err(DbError::NotFound { entity: Entity::Section, key }) => assert_eq!(Key::Id(99), key),
+            other => panic!("Expected a missing section, got {:?}", other),
+        }
+        assert!(db.remove_job_type(99).unwrap_err().is_not_found());
+        assert_eq!("No report with id 7 found", db.remove_report(7).unwrap_err().to_string());
+
+        match db.add_job_specification(99, 1) {
+            Err(DbError::ForeignKey { entity: Entity::JobSpecification, key }) => assert_eq!(Key::Id(1), key),
+            other => panic!("Expected a foreign key violation, got {:?}", other),
+        }
+
+        let mut unreachable = DBQualityControlHandle::new("tests/database_test/missing/db.db".to_string());
+        assert!(matches!(unreachable.get_all_job_types(), Err(DbError::Connection(_))));
+    }
+
+    #[test]
+    fn remove_used_section() {
+        let mut db = DBQualityControlHandle::new("tests/database_test/db_remove_used_section.db".to_string());
+
+        setup_testing_env(&db.database_path).unwrap();
+        setup_data(&mut db);
+        db.add_additive_section("Cover Page".to_string()).unwrap();
+        db.add_job_specification(1, 1).unwrap();
+
+        // Specifications of the section lose it, they are not left pointing to a removed section.
+        db.remove_section(1).unwrap();
+
+        assert_eq!(None, db.get_specification(1).unwrap().section);
+        assert_eq!(0, db.get_all_additive_sections().unwrap().len());
+
+        let conn = Connection::open(&db.database_path).unwrap();
+        let dangling: u64 = conn.query_row("SELECT COUNT(*) FROM specification WHERE section_id NOT IN (SELECT id FROM section)", [], |row| row.get(0)).unwrap();
+        assert_eq!(0, dangling);
+
+        db.remove_job_type(1).unwrap();
+        let job_specifications: u64 = conn.query_row("SELECT COUNT(*) FROM job_specification WHERE job_type_id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(0, job_specifications);
+    }
 }
@@ -1,6 +1,9 @@
 extends Resource
 class_name DatabaseAPI
 
+# Message of a failed write, e.g. a section name that is already used.
+signal database_error(message)
+
 var db: SQLDatabaseAPI
 
 func _init():
@@ -49,10 +52,21 @@ func all_job_header() -> Array:
 func update_section(section: SectionData):
 	print_debug("Updating section")
 	db.update_section(section.get_id(),section.get_content())
+	check_error()
 
 func update_specification(spec: SpecificationData):
 	db.update_specification(spec.id,spec.get_content(),spec.section.id)
 	print_debug("Updating spec")
+	check_error()
+
+# Emits the message of the last call when it failed, returns true on success.
+func check_error() -> bool:
+	var message = db.get_last_error()
+	if message != "":
+		emit_signal("database_error", message)
+		return false
+
+	return true
 
 func update_data(data):
 	print_debug("Updating data!!")
@@ -10,6 +10,7 @@ onready var specification_view = $TabSelection/TabContainer/Specifications
 onready var specification_editor = $SpecificationEditor
 onready var section_editor = $SectionEditor
 onready var job_maker = $JobMaker
+onready var error_popup = $ErrorPopup
 
 onready var exit_button = $TabSelection/Exit
 
@@ -22,6 +23,8 @@ func _ready():
 	section_editor.connect("update_database",db,"update_section")
 	specification_editor.connect("update_database",db,"update_specification")
 	specification_editor.db = db
+	db.connect("database_error",self,"show_database_error")
+	job_maker.connect("database_error",self,"show_database_error")
 
 func build_view():
 
@@ -35,7 +38,8 @@ func build_view():
 	specification_view.build_rows(specs, specification_editor)
 	
 	job_view.build_rows(jobs, null)
-	job_maker.connect("new_job_row", self, "create_job_row")
+	if not job_maker.is_connected("new_job_row", self, "create_job_row"):
+		job_maker.connect("new_job_row", self, "create_job_row")
 
 	for job_rows in job_view.get_rows():
 		job_rows.connect("edit_row_sig",self,"edit_job")
@@ -50,6 +54,17 @@ func clear():
 	section_view.clear()
 	specification_view.clear()
 
+# The edited row already shows the rejected change, the rows are read again from the database.
+func show_database_error(message: String):
+	error_popup.set_errors([message])
+	error_popup.popup_centered()
+
+	call_deferred("reload_view")
+
+func reload_view():
+	clear()
+	build_view()
+
 
 
 ##################
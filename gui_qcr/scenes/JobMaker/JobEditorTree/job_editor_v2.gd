@@ -186,17 +186,28 @@ func set_check_status(p_root: TreeItem, status: bool, tex: Texture):
 		
 		c_item = c_item.get_next()
 		
-func add_new_job_specifications(job_type_id: int, new_specifications: Array, job_db: SQLJobDatabaseAPI):
+# Both return the messages of the specifications that could not be changed.
+func add_new_job_specifications(job_type_id: int, new_specifications: Array, job_db: SQLJobDatabaseAPI) -> Array:
+	var errors = []
 	for spec in new_specifications:
 		var spec_id = spec.get_id()
 		
 		job_db.add_job_specification(job_type_id, spec_id)
+		if job_db.get_last_error() != "":
+			errors.append(job_db.get_last_error())
+	
+	return errors
 		
-func remove_job_specifications(job_type_id: int, remove_specifications: Array, job_db: SQLJobDatabaseAPI):
+func remove_job_specifications(job_type_id: int, remove_specifications: Array, job_db: SQLJobDatabaseAPI) -> Array:
+	var errors = []
 	for spec in remove_specifications:
 		var spec_id = spec.get_id()
 		
 		job_db.remove_job_specification(job_type_id, spec_id)
+		if job_db.get_last_error() != "":
+			errors.append(job_db.get_last_error())
+	
+	return errors
 
 func _on_Tree_button_pressed(item: TreeItem, column, id):
 	var button_id = item.get_button_by_id(column, id)
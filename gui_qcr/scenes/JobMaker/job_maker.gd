@@ -8,6 +8,7 @@ onready var job_editor = $JobEditorTree
 
 signal update_view(data)
 signal new_job_row(data)
+signal database_error(message)
 
 var job_type: JobHeaderData
 var db: DatabaseAPI
@@ -39,6 +40,7 @@ func clear_tree():
 func _on_Apply_pressed():
 	var job_additions_and_removals = job_editor.get_job_changes(job_db)
 	var job_type = job_additions_and_removals["job_type"]
+	var errors = []
 	
 	if job_type == null:
 		if job_line_edit.text == "":
@@ -53,12 +55,19 @@ func _on_Apply_pressed():
 			var job_type_name = job_line_edit.text
 			job_db.add_job_type(job_type_name)
 			
+			# e.g. the name is already used, the specifications would go to that job.
+			# The maker stays open to pick another name.
+			if job_db.get_last_error() != "":
+				show_errors([job_db.get_last_error()])
+				
+				return
+			
 			var job_id = job_db.get_job_type_id(job_type_name)
 			
 			
 			if job_id != null:
-				job_editor.add_new_job_specifications(job_id, new_specs, job_db)
-				job_editor.remove_job_specifications(job_id, remove_specs, job_db)
+				errors += job_editor.add_new_job_specifications(job_id, new_specs, job_db)
+				errors += job_editor.remove_job_specifications(job_id, remove_specs, job_db)
 				
 				job_type = JobHeaderData.new()
 				job_type.id = job_id
@@ -76,18 +85,28 @@ func _on_Apply_pressed():
 		# Update name
 		if job_line_edit.text != "":
 			if job_type.job_name != job_line_edit.text:
-				job_type.job_name = job_line_edit.text
-				
 				# Update jobe type in database.
-				job_db.update_job_type_name(job_type.id, job_type.job_name)
+				job_db.update_job_type_name(job_type.id, job_line_edit.text)
+				
+				# The old name is kept on failure.
+				if job_db.get_last_error() != "":
+					errors.append(job_db.get_last_error())
+				else:
+					job_type.job_name = job_line_edit.text
 		
-		job_editor.add_new_job_specifications(job_type.get_id(), new_specs, job_db)
-		job_editor.remove_job_specifications(job_type.get_id(), remove_specs, job_db)
+		errors += job_editor.add_new_job_specifications(job_type.get_id(), new_specs, job_db)
+		errors += job_editor.remove_job_specifications(job_type.get_id(), remove_specs, job_db)
 		
 		
 	emit_signal("update_view", job_type)
 	self.hide()
 	print(job_additions_and_removals)
+	
+	if errors.size() > 0:
+		show_errors(errors)
+
+func show_errors(errors: Array):
+	emit_signal("database_error", PoolStringArray(errors).join("\n"))
 
 
 func _on_Cancel_pressed():
@@ -4,6 +4,7 @@ class_name QCReportView
 # A display for godot to interact with the QCreport structure defined in Rust.
 
 onready var questionnaire_view = $QuestionnaireView
+onready var error_popup = $ErrorPopup
 
 enum report_state {EMPTY, WRITING_REPORT}
 var current_state = report_state.EMPTY
@@ -21,13 +22,13 @@ func set_report(p_report: QCReport):
 	if report != null:
 		if questionnaire_view.is_connected("update_questionnaire_notes", report, "update_form_notes"):
 			questionnaire_view.disconnect("update_questionnaire_notes")
-		if questionnaire_view.is_connected("update_questionnaire_status", report, "update_form_status"):
-			questionnaire_view.disconnect("update_questionnaire_status", report, "update_form_status")
+		if questionnaire_view.is_connected("update_questionnaire_response", report, "update_form_response"):
+			questionnaire_view.disconnect("update_questionnaire_response", report, "update_form_response")
 	
 	report = p_report
 	
 	questionnaire_view.connect("update_questionnaire_notes", report,"update_form_notes")
-	questionnaire_view.connect("update_questionnaire_status",report,"update_form_status")
+	questionnaire_view.connect("update_questionnaire_response",report,"update_form_response")
 	questionnaire_view.exit_button.connect("pressed", self,"show_report")
 
 func generate_pdf():
@@ -42,19 +43,40 @@ func generate_pdf():
 			_:
 				print_debug("Not speified OS")
 				
-		report.write_csv(ProjectSettings.globalize_path("user://forms.csv"),
-		ProjectSettings.globalize_path("user://plot.csv"))
+		if not report.write_csv(ProjectSettings.globalize_path("user://forms.csv"),
+		ProjectSettings.globalize_path("user://plot.csv")):
+			printerr("ERROR: Unable to write the CSV files.")
 		
 func windows_pdf():
 	pass
 	
 func linux_pdf():
-	report.generate_report("testing_linux.pdf",["/usr/share/fonts/carlito/", "Carlito"])
+	# No font style, the font bundled with the report is used.
+	if not report.generate_report("testing_linux.pdf", "Quality Control Report", null, false):
+		if report.is_complete():
+			show_error(report.get_last_error())
+		else:
+			show_unanswered()
+
+func show_error(message: String):
+	error_popup.set_errors([message])
+	error_popup.popup_centered()
+
+# Incomplete reports are not generated, the questionnaire highlights what is left to answer.
+func show_unanswered():
+	var unanswered = report.unanswered_forms()
+	if unanswered.size() == 0:
+		return
+	
+	for section in unanswered:
+		printerr("Unanswered in " + section[0] + ": " + str(section[1].size()) + " specifications")
+	
+	_on_EditReport_pressed()
 
 func _on_EditReport_pressed():
 	print(report.all_form_fields())
 	questionnaire_view.show()
-	questionnaire_view.build_questionnaire(report.all_form_fields())
+	questionnaire_view.build_questionnaire(report.all_form_fields(), report)
 	$HSplitContainer.hide()
 
 func _on_Button_pressed():
@@ -1,9 +1,35 @@
 extends OptionButton
 class_name OptionStatusButton
 
-signal update_form_status(form_id, status)
+signal update_form_response(form_id, code)
+
+# Status the report gives forms that are not answered yet.
+const UNANSWERED_STATUS = 3
+const UNANSWERED_COLOR = Color(1.0, 0.6, 0.6)
 
 var unit_form_id
 
+# Shown until an option is chosen, it can not be chosen itself.
+func add_unanswered():
+	add_item("Unanswered")
+	set_item_disabled(get_item_count() - 1, true)
+
+# Options of the response scale of the form, the code is kept as the item metadata.
+func add_option(code: String, label: String):
+	add_item(label)
+	set_item_metadata(get_item_count() - 1, code)
+
+func select_code(code: String):
+	for index in range(get_item_count()):
+		if get_item_metadata(index) == code:
+			select(index)
+			return
+
+# Unanswered forms stand out until they are answered.
+func select_unanswered():
+	select(0)
+	modulate = UNANSWERED_COLOR
+
 func _on_OptionStatusButton_item_selected(index):
-	emit_signal("update_form_status", unit_form_id, index)
+	modulate = Color.white
+	emit_signal("update_form_response", unit_form_id, get_item_metadata(index))
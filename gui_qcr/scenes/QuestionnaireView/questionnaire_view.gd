@@ -1,7 +1,7 @@
 extends Control
 
 signal update_questionnaire_notes(form_id, text)
-signal update_questionnaire_status(form_id, status)
+signal update_questionnaire_response(form_id, code)
 
 
 var edit_cell_packed = preload("res://scenes/QuestionnaireView/EditCell/EditCell.tscn")
@@ -10,19 +10,39 @@ var option_status_button = preload("res://scenes/QuestionnaireView/OptionStatusB
 onready var grid = $PanelContainer/ScrollContainer/GridContainer
 onready var exit_button = $VSplitContainer/Button
 
+# Kept to rebuild the view when an answer shows or hides other forms.
+var report: QCReport
+var forms: Array
+
 func _ready():
 	grid.columns = 4
 
-func build_questionnaire(forms: Array):
+# The options of each form are read from *report*, forms can use different response scales.
+func build_questionnaire(p_forms: Array, p_report: QCReport):
+	forms = p_forms
+	report = p_report
+	clear()
+	
 	for form in forms:
 		var section_name = form[1]
 		var specification_content = form[2]
 		var form_id = form[0]
 		
-		add_unit_form_view(form_id, section_name, specification_content, 2)
+		# Forms hidden by the rules of the job are not asked.
+		if not report.is_form_visible(form_id):
+			continue
+		
+		var response_code = report.form_response(form_id)[0]
+		var answered = report.form_status(form_id) != OptionStatusButton.UNANSWERED_STATUS
+		add_unit_form_view(form_id, section_name, specification_content, report.form_response_options(form_id), response_code, answered)
+
+func clear():
+	for cell in grid.get_children():
+		grid.remove_child(cell)
+		cell.queue_free()
 
 # Adds the unit form row.
-func add_unit_form_view(form_id: int, section_name: String, specification_content: String, status_id: int):
+func add_unit_form_view(form_id: int, section_name: String, specification_content: String, response_options: Array, response_code: String, answered: bool):
 	var section_cell = edit_cell_packed.instance()
 	var specification_cell = edit_cell_packed.instance()
 	
@@ -39,25 +59,34 @@ func add_unit_form_view(form_id: int, section_name: String, specification_conten
 	
 	notes.connect("update_form", self,"update_form_notes")
 	
-	var options: OptionStatusButton = build_options(form_id)
+	var options: OptionStatusButton = build_options(form_id, response_options)
 	grid.add_child(options)
-	options.selected = 2
-	options.connect("update_form_status", self, "update_form_status")
+	if answered:
+		options.select_code(response_code)
+	else:
+		options.select_unanswered()
+	options.connect("update_form_response", self, "update_form_response")
 	
 	
 func update_form_notes(form_id: int, text: String):
 	emit_signal("update_questionnaire_notes", form_id, text)
 	
-func update_form_status(form_id: int, status: int):
-	emit_signal("update_questionnaire_status", form_id, status)
+func update_form_response(form_id: int, code: String):
+	emit_signal("update_questionnaire_response", form_id, code)
+	# Deferred, the option button sending the answer is freed by the rebuild.
+	call_deferred("rebuild")
+
+func rebuild():
+	build_questionnaire(forms, report)
 	
 
-func build_options(id: int) -> OptionStatusButton:
+# *response_options* are the [code, label] of the options of the form.
+func build_options(id: int, response_options: Array) -> OptionStatusButton:
 	var option_button = option_status_button.instance()
 	
-	option_button.add_item("Ok", 0)
-	option_button.add_item("NO", 1)
-	option_button.add_item("N/A", 2)
+	option_button.add_unanswered()
+	for option in response_options:
+		option_button.add_option(option[0], option[1])
 	option_button.unit_form_id = id
 	
 	return option_button